@@ -0,0 +1,259 @@
+//! Derive macros of the `mssql_client` crate, enabled with its `derive` feature.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Field, Fields, Ident, Lit, Variant};
+
+/// Implements `FromColumn` for an enum read from a string column.
+///
+/// Each variant matches its name, or the string given with `#[sql("...")]`.
+///
+/// # Example
+/// ```
+/// use mssql_client::FromColumnStr;
+///
+/// #[derive(FromColumnStr)]
+/// enum Status {
+///     #[sql("A")]
+///     Active,
+///     #[sql("I")]
+///     Inactive,
+///     Pending,
+/// }
+/// ```
+#[proc_macro_derive(FromColumnStr, attributes(sql))]
+pub fn from_column_str(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, Kind::Str)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implements `FromColumn` for an enum read from an integer column.
+///
+/// Each variant matches its discriminant, or the integer given with `#[sql(...)]`.
+///
+/// # Example
+/// ```
+/// use mssql_client::FromColumnInt;
+///
+/// #[derive(FromColumnInt)]
+/// enum Priority {
+///     Low = 1,
+///     High = 2,
+///     #[sql(99)]
+///     Urgent,
+/// }
+/// ```
+#[proc_macro_derive(FromColumnInt, attributes(sql))]
+pub fn from_column_int(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, Kind::Int)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implements `FromRow` and `FromRowOffset` for a struct, reading its fields from the
+/// columns in order.
+///
+/// A field marked with `#[from_row(flatten)]` is read with its own `FromRowOffset` from the
+/// following columns, so that a row covering joined tables can be split into nested structs.
+///
+/// # Example
+/// ```
+/// use mssql_client::FromRow;
+///
+/// #[derive(FromRow)]
+/// struct Customer {
+///     id: i32,
+///     name: String,
+/// }
+///
+/// // SELECT o.Id, c.Id, c.Name, o.Total FROM Orders o JOIN Customers c ON ...
+/// #[derive(FromRow)]
+/// struct Order {
+///     id: i32,
+///     #[from_row(flatten)]
+///     customer: Customer,
+///     total: f64,
+/// }
+/// ```
+#[proc_macro_derive(FromRow, attributes(from_row))]
+pub fn from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_row(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Int,
+    Str,
+}
+
+fn expand(input: &DeriveInput, kind: Kind) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => return Err(Error::new_spanned(input, "expected an enum")),
+    };
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "generic enums are not supported"));
+    }
+
+    let mut arms = Vec::with_capacity(data.variants.len());
+
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(variant, "expected a variant without fields"));
+        }
+
+        let name = &variant.ident;
+        let value = variant_value(ident, variant, kind)?;
+
+        arms.push(match kind {
+            Kind::Int => quote!(v if v == #value => ::std::result::Result::Ok(#ident::#name),),
+            Kind::Str => quote!(#value => ::std::result::Result::Ok(#ident::#name),),
+        });
+    }
+
+    let ty = match kind {
+        Kind::Int => quote!(i32),
+        Kind::Str => quote!(&'a str),
+    };
+
+    let name = ident.to_string();
+
+    Ok(quote! {
+        impl<'a> ::mssql_client::FromColumn<'a> for #ident {
+            type Value = #ty;
+
+            fn from_column(v: Self::Value) -> ::mssql_client::Result<Self> {
+                match v {
+                    #(#arms)*
+                    _ => ::std::result::Result::Err(::mssql_client::Error::String(
+                        ::std::format!("Invalid value `{}` for `{}`.", v, #name),
+                    )),
+                }
+            }
+        }
+    })
+}
+
+fn expand_from_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(Error::new_spanned(input, "expected a struct")),
+    };
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "generic structs are not supported"));
+    }
+
+    // the number of columns of each field, the index of a field is the offset plus the
+    // columns of the previous fields
+    let mut counts = Vec::with_capacity(fields.len());
+    let mut reads = Vec::with_capacity(fields.len());
+
+    for field in fields {
+        let ty = &field.ty;
+        let index = quote!(offset #(+ #counts)*);
+
+        let (read, columns) = if is_flatten(field)? {
+            (
+                quote!(<#ty as ::mssql_client::FromRowOffset>::from_row_offset(row, #index)?),
+                quote!(<#ty as ::mssql_client::FromRowOffset>::COLUMNS),
+            )
+        } else {
+            let read = match &field.ident {
+                Some(name) => {
+                    let name = name.to_string();
+                    quote!(row.get_named_err(#index, #name)?)
+                }
+                None => quote!(row.get(#index)?),
+            };
+
+            (read, quote!(1))
+        };
+
+        reads.push(match &field.ident {
+            Some(name) => quote!(#name: #read),
+            None => read,
+        });
+
+        counts.push(columns);
+    }
+
+    let body = match fields {
+        Fields::Named(_) => quote!(#ident { #(#reads,)* }),
+        Fields::Unnamed(_) => quote!(#ident(#(#reads,)*)),
+        Fields::Unit => quote!(#ident),
+    };
+
+    Ok(quote! {
+        impl ::mssql_client::FromRowOffset for #ident {
+            const COLUMNS: usize = 0 #(+ #counts)*;
+
+            #[allow(unused_variables)]
+            fn from_row_offset(
+                row: &::mssql_client::Row,
+                offset: usize,
+            ) -> ::mssql_client::Result<Self> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+
+        impl ::mssql_client::FromRow for #ident {
+            fn from_row(row: &::mssql_client::Row) -> ::mssql_client::Result<Self> {
+                <Self as ::mssql_client::FromRowOffset>::from_row_offset(row, 0)
+            }
+        }
+    })
+}
+
+/// Returns `true` if the field has the `#[from_row(flatten)]` attribute.
+fn is_flatten(field: &Field) -> syn::Result<bool> {
+    match field.attrs.iter().find(|a| a.path.is_ident("from_row")) {
+        Some(attr) => {
+            let arg = attr.parse_args::<Ident>()?;
+
+            if arg == "flatten" {
+                Ok(true)
+            } else {
+                Err(Error::new_spanned(arg, "expected `flatten`"))
+            }
+        }
+        None => Ok(false),
+    }
+}
+
+/// The value matched by a variant, from its `#[sql(...)]` attribute, its discriminant or its name.
+fn variant_value(ident: &Ident, variant: &Variant, kind: Kind) -> syn::Result<TokenStream2> {
+    let name = &variant.ident;
+    let attr = variant.attrs.iter().find(|a| a.path.is_ident("sql"));
+
+    let lit = match attr {
+        Some(attr) => Some(attr.parse_args::<Lit>()?),
+        None => None,
+    };
+
+    match (kind, lit) {
+        (Kind::Int, Some(Lit::Int(i))) => Ok(quote!(#i)),
+        (Kind::Str, Some(Lit::Str(s))) => Ok(quote!(#s)),
+        (_, Some(lit)) => Err(Error::new_spanned(lit, "unexpected literal type")),
+        (Kind::Int, None) if variant.discriminant.is_some() => Ok(quote!(#ident::#name as i32)),
+        (Kind::Int, None) => Err(Error::new_spanned(
+            variant,
+            "expected a discriminant or a #[sql(...)] attribute",
+        )),
+        (Kind::Str, None) => {
+            let name = name.to_string();
+            Ok(quote!(#name))
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{Parameter, Params};
+use std::borrow::Cow;
+
+/// A string parameter declared as `VARCHAR` instead of `NVARCHAR`, so that a comparison
+/// with a `VARCHAR` column seeks its index instead of converting every value of the column.
+///
+/// The statement is run through `sp_executesql`, which converts the value to the code page of
+/// the database; the characters that it does not contain are replaced by `?`.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Ansi, Connection, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let (_, v): (_, String) = Connection::from_env("MSSQL_DB")
+///         .await?
+///         .query_scalar(
+///             "SELECT CAST(SQL_VARIANT_PROPERTY(@p1, 'BaseType') AS NVARCHAR(128))",
+///             Ansi("abc"),
+///         )
+///         .await?;
+///
+///     assert_eq!("varchar", v);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Ansi<'a>(pub &'a str);
+
+impl<'a> Params<'a> for Ansi<'a> {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::AnsiString(Some(Cow::Borrowed(self.0))))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::AnsiString(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Result};
+
+    #[tokio::test]
+    async fn ansi() -> Result<()> {
+        let connection = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #T (V VARCHAR(10) PRIMARY KEY)", ())
+            .await?
+            .execute("INSERT #T VALUES (@p1), (@p2)", (Ansi("a"), Ansi("b")))
+            .await?;
+
+        let (_, rows): (_, Vec<String>) = connection
+            .query("SELECT V FROM #T WHERE V = @p1 OR V = @p2", (Ansi("b"), None::<Ansi>))
+            .await?;
+
+        assert_eq!(vec!["b"], rows);
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{Connection, Error, Result};
+use futures03::future::LocalBoxFuture;
+use std::time::Duration;
+
+/// The mode of an application lock, see `sp_getapplock`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LockMode {
+    Shared,
+    Update,
+    IntentShared,
+    IntentExclusive,
+    Exclusive,
+}
+
+impl LockMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Shared => "Shared",
+            Self::Update => "Update",
+            Self::IntentShared => "IntentShared",
+            Self::IntentExclusive => "IntentExclusive",
+            Self::Exclusive => "Exclusive",
+        }
+    }
+}
+
+/// The outcome of [Connection::app_lock](struct.Connection.html#method.app_lock).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppLock {
+    /// The lock was granted immediately.
+    Granted,
+    /// The lock was granted after waiting for other sessions to release it.
+    GrantedAfterWait,
+    /// The lock was not granted before the timeout.
+    TimedOut,
+    /// The request was canceled.
+    Canceled,
+    /// The request was chosen as deadlock victim.
+    Deadlock,
+}
+
+impl AppLock {
+    /// Returns `true` if the lock is held.
+    pub fn is_granted(self) -> bool {
+        self == Self::Granted || self == Self::GrantedAfterWait
+    }
+
+    fn from_code(code: i32) -> Result<Self> {
+        match code {
+            0 => Ok(Self::Granted),
+            1 => Ok(Self::GrantedAfterWait),
+            -1 => Ok(Self::TimedOut),
+            -2 => Ok(Self::Canceled),
+            -3 => Ok(Self::Deadlock),
+            _ => Err(Error::String(format!("sp_getapplock failed with code {}.", code))),
+        }
+    }
+}
+
+impl Connection {
+    /// Acquires an application lock on `resource` for the session using `sp_getapplock`,
+    /// waiting up to `timeout` for it to be granted.
+    ///
+    /// The lock is held until [release_app_lock](#method.release_app_lock) is called or
+    /// the connection is closed. A session can acquire the same lock several times and
+    /// must release it as many times.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, LockMode, Result};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let (connection, lock) = connection
+    ///         .app_lock("nightly-job", LockMode::Exclusive, Duration::from_secs(5))
+    ///         .await?;
+    ///
+    ///     if lock.is_granted() {
+    ///         // run the job...
+    ///         connection.release_app_lock("nightly-job").await?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn app_lock<'a>(
+        self,
+        resource: &'a str,
+        mode: LockMode,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'a, Result<(Self, AppLock)>> {
+        let sql = r#"
+            DECLARE @r INT;
+            EXEC @r = sp_getapplock
+                @Resource = @p1, @LockMode = @p2, @LockOwner = 'Session', @LockTimeout = @p3;
+            SELECT @r"#;
+
+        let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+
+        Box::pin(async move {
+            let (conn, codes): (_, Vec<i32>) =
+                self.query(sql, (resource, mode.as_str(), timeout)).await?;
+
+            let code = codes.into_iter().next().unwrap_or(-999);
+            Ok((conn, AppLock::from_code(code)?))
+        })
+    }
+
+    /// Releases an application lock acquired by the session with
+    /// [app_lock](#method.app_lock) using `sp_releaseapplock`.
+    ///
+    /// An error is returned if the session does not hold the lock.
+    pub fn release_app_lock<'a>(self, resource: &'a str) -> LocalBoxFuture<'a, Result<Self>> {
+        let sql = r#"
+            DECLARE @r INT;
+            EXEC @r = sp_releaseapplock @Resource = @p1, @LockOwner = 'Session';
+            SELECT @r"#;
+
+        Box::pin(async move {
+            let (conn, codes): (_, Vec<i32>) = self.query(sql, resource).await?;
+
+            match codes.into_iter().next() {
+                Some(0) => Ok(conn),
+                _ => Err(Error::String(format!(
+                    "Application lock `{}` is not held by the session.",
+                    resource
+                ))),
+            }
+        })
+    }
+}
+
+#[test]
+fn app_lock_from_code_works() {
+    assert_eq!(AppLock::GrantedAfterWait, AppLock::from_code(1).unwrap());
+    assert!(!AppLock::from_code(-1).unwrap().is_granted());
+    assert!(AppLock::from_code(-999).is_err());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn app_lock() -> Result<()> {
+        let (connection, lock) = Connection::from_env("MSSQL_DB")
+            .await?
+            .app_lock("mssql_client_test", LockMode::Exclusive, Duration::from_secs(1))
+            .await?;
+
+        assert!(lock.is_granted());
+
+        let connection = connection.release_app_lock("mssql_client_test").await?;
+        assert!(connection.release_app_lock("mssql_client_test").await.is_err());
+        Ok(())
+    }
+}
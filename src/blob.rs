@@ -0,0 +1,490 @@
+use crate::{
+    context::sp_executesql, utils::quote_ident, Command, Connection, Error, Parameter, Params,
+    Result, Row, SchemaIdent, Transaction,
+};
+use futures03::{
+    future::LocalBoxFuture,
+    io::{AsyncRead, AsyncReadExt},
+};
+use std::{borrow::Cow, fmt::Debug, io::Write};
+use tracing::instrument;
+
+/// The type of the value read in chunks.
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Binary,
+    Text,
+}
+
+impl Connection {
+    /// Reads a `varbinary(max)` value in chunks of `chunk_size` bytes and writes them to
+    /// `writer`, so that a large value is never held in memory. Returns the number of
+    /// bytes written.
+    ///
+    /// The query must return one row of one column. The value is first copied into a
+    /// temporary table with `INSERT ... EXEC sp_executesql`, so the query can be any batch,
+    /// such as a `SELECT` with a CTE or an `ORDER BY`, or an `EXEC` of a procedure, but cannot
+    /// itself use `INSERT ... EXEC`. It is then read with `SUBSTRING`, one round trip per
+    /// chunk. Nothing is written for a null value and
+    /// [Error::NoRows](enum.Error.html#variant.NoRows) is returned if the query selects no
+    /// row.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    /// use std::fs::File;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let file = File::create(std::env::temp_dir().join("blob.bin"))?;
+    ///     let sql = "SELECT CAST(REPLICATE(CAST('a' AS VARCHAR(MAX)), 100000) AS VARBINARY(MAX))";
+    ///
+    ///     let (_connection, len) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .read_blob(sql, (), 8192, file)
+    ///         .await?;
+    ///
+    ///     assert_eq!(100_000, len);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_blob<'a, S, P, W>(
+        self,
+        sql: S,
+        params: P,
+        chunk_size: usize,
+        writer: W,
+    ) -> LocalBoxFuture<'a, Result<(Self, u64)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        W: Write + 'a,
+    {
+        Box::pin(self.read_chunks_imp(sql, params, chunk_size, writer, Kind::Binary))
+    }
+
+    /// Reads a `nvarchar(max)` or `varchar(max)` value in chunks of `chunk_size` characters
+    /// and writes them to `writer` in UTF-8. Returns the number of bytes written.
+    ///
+    /// The characters are counted in UTF-16 code units, a chunk that would end between the
+    /// two halves of a surrogate pair is shortened, or extended for a chunk size of 1.
+    ///
+    /// See [read_blob](#method.read_blob) for how the value is read.
+    pub fn read_text<'a, S, P, W>(
+        self,
+        sql: S,
+        params: P,
+        chunk_size: usize,
+        writer: W,
+    ) -> LocalBoxFuture<'a, Result<(Self, u64)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        W: Write + 'a,
+    {
+        Box::pin(self.read_chunks_imp(sql, params, chunk_size, writer, Kind::Text))
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "Connection::read_chunks",
+        skip(self, writer),
+        err
+    )]
+    async fn read_chunks_imp<'a, S, P, W>(
+        self,
+        sql: S,
+        params: P,
+        chunk_size: usize,
+        writer: W,
+        kind: Kind,
+    ) -> Result<(Self, u64)>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        W: Write + 'a,
+    {
+        if chunk_size == 0 {
+            return Err(Error::Str("The chunk size must be greater than zero."));
+        }
+
+        let ty = match kind {
+            Kind::Binary => "VARBINARY(MAX)",
+            Kind::Text => "NVARCHAR(MAX)",
+        };
+
+        // created outside of the parameterized batch, which would drop it on completion
+        let create = format!(
+            r#"
+            IF OBJECT_ID('tempdb..#read_chunks') IS NOT NULL DROP TABLE #read_chunks;
+            CREATE TABLE #read_chunks (v {})"#,
+            ty
+        );
+
+        let mut p = Vec::<Parameter>::new();
+        params.params(&mut p);
+
+        let insert = format!(
+            "INSERT #read_chunks (v) {};\nSELECT @@ROWCOUNT",
+            sp_executesql(&sql.into(), &p)
+        );
+
+        let conn = self.execute(create, ()).await?;
+        let (conn, count): (_, i32) = conn.query_scalar(insert, p).await?;
+
+        match count {
+            0 => return Err(Error::NoRows),
+            1 => {}
+            n => return Err(Error::TooManyRows(n as u64)),
+        }
+
+        let chunk_sql = chunk_sql(kind);
+        let mut state = (conn, writer, 0u64, 1i64);
+
+        loop {
+            let (conn, writer, total, offset) = state;
+
+            let (conn, (read, len, writer)) = conn
+                .query_fold(
+                    chunk_sql,
+                    (offset, chunk_size as i64),
+                    (0usize, 0i64, writer),
+                    move |(_, _, mut writer), row| {
+                        let read = write_chunk(row, kind, &mut writer)?;
+                        Ok((read, row.get(1)?, writer))
+                    },
+                )
+                .await?;
+
+            let total = total + read as u64;
+
+            if read == 0 {
+                let conn = conn.execute("DROP TABLE #read_chunks", ()).await?;
+                return Ok((conn, total));
+            }
+
+            state = (conn, writer, total, offset + len);
+        }
+    }
+}
+
+impl Connection {
+    /// Writes the content of `reader` into a `varbinary(max)` column in chunks of
+    /// `chunk_size` bytes, so that a large file is never held in memory. Returns the
+    /// number of bytes written.
+    ///
+    /// The row is found by the `keys` columns, with one value per key in `key_values`,
+    /// and must exist. The column is set to `0x`, then each chunk is appended with
+    /// `UPDATE .WRITE`, one round trip per chunk. Use a transaction to avoid leaving a
+    /// partial value if an error occurs, see
+    /// [Transaction::write_blob](struct.Transaction.html#method.write_blob).
+    ///
+    /// [Error::NoRows](enum.Error.html#variant.NoRows) or
+    /// [Error::TooManyRows](enum.Error.html#variant.TooManyRows) is returned if the keys
+    /// do not match exactly one row; the row is left unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use futures03::io::AllowStdIo;
+    /// use mssql_client::{Connection, Result};
+    /// use std::fs::File;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let files = "dbo.Files".parse()?;
+    ///     let file = AllowStdIo::new(File::open("Cargo.toml")?);
+    ///
+    ///     let (_connection, len) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .write_blob(&files, "Data", &["Id"], 1, file, 64 * 1024)
+    ///         .await?;
+    ///
+    ///     println!("{} bytes written", len);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_blob<'a, P, R>(
+        self,
+        table: &'a SchemaIdent,
+        column: &'a str,
+        keys: &'a [&'a str],
+        key_values: P,
+        reader: R,
+        chunk_size: usize,
+    ) -> LocalBoxFuture<'a, Result<(Self, u64)>>
+    where
+        P: Params<'a> + 'a,
+        R: AsyncRead + Unpin + 'a,
+    {
+        Box::pin(write_chunks(self, table, column, keys, key_values, reader, chunk_size))
+    }
+}
+
+impl Transaction {
+    /// Writes the content of `reader` into a `varbinary(max)` column in chunks.
+    ///
+    /// See [Connection::write_blob](struct.Connection.html#method.write_blob).
+    pub fn write_blob<'a, P, R>(
+        self,
+        table: &'a SchemaIdent,
+        column: &'a str,
+        keys: &'a [&'a str],
+        key_values: P,
+        reader: R,
+        chunk_size: usize,
+    ) -> LocalBoxFuture<'a, Result<(Self, u64)>>
+    where
+        P: Params<'a> + 'a,
+        R: AsyncRead + Unpin + 'a,
+    {
+        Box::pin(write_chunks(self, table, column, keys, key_values, reader, chunk_size))
+    }
+}
+
+#[instrument(
+    level = "debug",
+    name = "write_blob",
+    skip(command, key_values, reader),
+    err
+)]
+async fn write_chunks<'a, C, P, R>(
+    command: C,
+    table: &SchemaIdent,
+    column: &str,
+    keys: &[&str],
+    key_values: P,
+    mut reader: R,
+    chunk_size: usize,
+) -> Result<(C, u64)>
+where
+    C: Command + 'a,
+    P: Params<'a>,
+    R: AsyncRead + Unpin,
+{
+    if chunk_size == 0 {
+        return Err(Error::Str("The chunk size must be greater than zero."));
+    }
+
+    let mut p = Vec::new();
+    key_values.params(&mut p);
+
+    if keys.is_empty() || p.len() != keys.len() {
+        return Err(Error::String(format!(
+            "Blob row has {} key values for {} keys.",
+            p.len(),
+            keys.len()
+        )));
+    }
+
+    let list = keys.iter().map(|k| quote_ident(k)).collect::<Vec<_>>().join(", ");
+
+    // the UNION drops the identity property that SELECT INTO would copy
+    let create = format!(
+        r#"
+        IF OBJECT_ID('tempdb..#write_blob') IS NOT NULL DROP TABLE #write_blob;
+        SELECT TOP 0 {0} INTO #write_blob FROM {1} UNION ALL SELECT TOP 0 {0} FROM {1}"#,
+        list, table
+    );
+
+    let (init, append) = write_sql(table, column, keys);
+    let c = command.execute(create, ()).await?;
+    let (mut c, count): (_, i32) = c.query_scalar(init, p).await?;
+
+    match count {
+        0 => return Err(Error::NoRows),
+        1 => {}
+        n => return Err(Error::TooManyRows(n as u64)),
+    }
+
+    let mut buf = vec![0; chunk_size];
+    let mut total = 0u64;
+
+    loop {
+        let mut len = 0;
+
+        while len < chunk_size {
+            match reader.read(&mut buf[len..]).await? {
+                0 => break,
+                n => len += n,
+            }
+        }
+
+        if len > 0 {
+            c = c.execute(append.clone(), buf[..len].to_vec()).await?;
+            total += len as u64;
+        }
+
+        if len < chunk_size {
+            let c = c.execute("DROP TABLE #write_blob", ()).await?;
+            return Ok((c, total));
+        }
+    }
+}
+
+/// The statements initializing the column of the row matching `#write_blob` and appending
+/// a chunk to it.
+fn write_sql(table: &SchemaIdent, column: &str, keys: &[&str]) -> (String, String) {
+    let list = keys.iter().map(|k| quote_ident(k)).collect::<Vec<_>>().join(", ");
+
+    let values = (1..=keys.len())
+        .map(|i| format!("@p{}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let on = keys
+        .iter()
+        .map(|k| format!("t.{0} = k.{0}", quote_ident(k)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let from = format!("FROM {} AS t INNER JOIN #write_blob AS k ON {}", table, on);
+    let column = quote_ident(column);
+
+    let init = format!(
+        r#"
+        INSERT #write_blob ({}) VALUES ({});
+        DECLARE @n INT = (SELECT COUNT(*) {});
+        IF @n = 1 UPDATE t SET {} = 0x {};
+        SELECT @n"#,
+        list, values, from, column, from
+    );
+
+    let append = format!("UPDATE t SET {}.WRITE(@p1, NULL, NULL) {}", column, from);
+    (init, append)
+}
+
+/// Selects the chunk at `@p1` of `@p2` units and its length in units, the text chunks
+/// being adjusted not to split a surrogate pair.
+fn chunk_sql(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Binary => "SELECT SUBSTRING(v, @p1, @p2), @p2 FROM #read_chunks",
+        Kind::Text => {
+            r#"
+            SELECT SUBSTRING(v, @p1, a.n), a.n
+            FROM #read_chunks
+            CROSS APPLY (
+                SELECT CASE
+                    WHEN UNICODE(SUBSTRING(v, @p1 + @p2 - 1, 1)) BETWEEN 55296 AND 56319
+                        THEN CASE WHEN @p2 > 1 THEN @p2 - 1 ELSE 2 END
+                    ELSE @p2
+                END
+            ) AS a (n)"#
+        }
+    }
+}
+
+/// Writes a chunk and returns its length in bytes, 0 for null or empty.
+fn write_chunk<W: Write>(row: &Row, kind: Kind, writer: &mut W) -> Result<usize> {
+    let bytes: Option<&[u8]> = match kind {
+        Kind::Binary => row.get(0)?,
+        Kind::Text => {
+            let s: Option<&str> = row.get(0)?;
+            s.map(str::as_bytes)
+        }
+    };
+
+    let bytes = bytes.unwrap_or_default();
+    writer.write_all(bytes)?;
+    Ok(bytes.len())
+}
+
+#[test]
+fn write_sql_works() {
+    let table = "dbo.Files".parse().unwrap();
+    let (init, append) = write_sql(&table, "Data", &["Id"]);
+
+    assert!(init.contains("INSERT #write_blob ([Id]) VALUES (@p1);"));
+    assert!(init.contains("IF @n = 1 UPDATE t SET [Data] = 0x FROM [dbo].[Files] AS t"));
+    assert_eq!(
+        "UPDATE t SET [Data].WRITE(@p1, NULL, NULL) FROM [dbo].[Files] AS t \
+        INNER JOIN #write_blob AS k ON t.[Id] = k.[Id]",
+        append
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_blob() -> Result<()> {
+        let mut out = Vec::new();
+        let (conn, len) = Connection::from_env("MSSQL_DB")
+            .await?
+            .read_blob("SELECT 0x0102030405 WHERE 1 = @p1", 1, 2, &mut out)
+            .await?;
+
+        assert_eq!(5, len);
+        assert_eq!(vec![1, 2, 3, 4, 5], out);
+
+        let mut out = Vec::new();
+        let (conn, len) = conn.read_text("SELECT N'été'", (), 2, &mut out).await?;
+
+        assert_eq!(5, len);
+        assert_eq!("été", String::from_utf8(out).unwrap());
+
+        let r = conn.read_blob("SELECT 0x01 WHERE 1 = 0", (), 2, Vec::new()).await;
+        assert!(matches!(r, Err(Error::NoRows)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_text_any_batch() -> Result<()> {
+        let sql = r#"
+            WITH t (v) AS (SELECT N'b' UNION ALL SELECT N'a')
+            SELECT TOP 1 v FROM t WHERE v <> @p1 ORDER BY v"#;
+
+        let mut out = Vec::new();
+        let (conn, _) = Connection::from_env("MSSQL_DB")
+            .await?
+            .read_text(sql, "x", 2, &mut out)
+            .await?;
+
+        assert_eq!("a", String::from_utf8(out).unwrap());
+
+        // the surrogate pair of the emoji is not split across chunks
+        for chunk_size in 1..4 {
+            let mut out = Vec::new();
+            let (_, len) = Connection::from_env("MSSQL_DB")
+                .await?
+                .read_text("SELECT N'a😀b😀'", (), chunk_size, &mut out)
+                .await?;
+
+            assert_eq!(10, len);
+            assert_eq!("a😀b😀", String::from_utf8(out).unwrap());
+        }
+
+        let mut out = Vec::new();
+        conn.read_text("SELECT v FROM (VALUES (N'z')) t(v) ORDER BY v", (), 2, &mut out)
+            .await?;
+
+        assert_eq!("z", String::from_utf8(out).unwrap());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_blob() -> Result<()> {
+        use futures03::io::AllowStdIo;
+
+        let table = "#Blob".parse()?;
+        let data = (0..10).collect::<Vec<u8>>();
+        let reader = AllowStdIo::new(std::io::Cursor::new(data.clone()));
+
+        let (conn, len) = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #Blob (Id INT, Data VARBINARY(MAX))", ())
+            .await?
+            .execute("INSERT #Blob VALUES (1, NULL), (2, NULL)", ())
+            .await?
+            .write_blob(&table, "Data", &["Id"], 2, reader, 4)
+            .await?;
+
+        assert_eq!(10, len);
+
+        let (_, rows): (_, Vec<Option<Vec<u8>>>) =
+            conn.query("SELECT Data FROM #Blob ORDER BY Id", ()).await?;
+
+        assert_eq!(vec![None, Some(data)], rows);
+        Ok(())
+    }
+}
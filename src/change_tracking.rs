@@ -0,0 +1,183 @@
+use crate::{utils::quote_ident, Connection, Error, FromRow, Result, SchemaIdent};
+use futures03::future::LocalBoxFuture;
+
+/// The operation of a [Change](struct.Change.html), from `SYS_CHANGE_OPERATION`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChangeOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A row changed since a version, as listed by
+/// [Connection::changes_since](struct.Connection.html#method.changes_since).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change<T> {
+    /// The version of the last change of the row, `SYS_CHANGE_VERSION`.
+    pub version: i64,
+    pub operation: ChangeOperation,
+    /// The columns of the table, in their declared order. For a deleted row, only the
+    /// primary key columns are set, the others are null.
+    pub row: T,
+}
+
+impl Connection {
+    /// Returns the version of the last committed transaction tracked by Change Tracking,
+    /// `None` when it is not enabled on the database.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let (_connection, version) = connection.change_tracking_current_version().await?;
+    ///     println!("{:?}", version);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn change_tracking_current_version(
+        self,
+    ) -> LocalBoxFuture<'static, Result<(Self, Option<i64>)>> {
+        self.query_scalar("SELECT CHANGE_TRACKING_CURRENT_VERSION()", ())
+    }
+
+    /// Lists the rows of a table inserted, updated or deleted after `version`, ordered by
+    /// version, using `CHANGETABLE(CHANGES ...)`.
+    ///
+    /// The table must have a primary key and Change Tracking enabled. An error is returned
+    /// when `version` is older than `CHANGE_TRACKING_MIN_VALID_VERSION`, the changes having
+    /// been cleaned up; the table must then be read again entirely.
+    ///
+    /// To sync incrementally, read the
+    /// [current version](#method.change_tracking_current_version) first, then the changes
+    /// since the version read by the previous sync, in a snapshot transaction.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Change, ChangeOperation, Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let orders = "dbo.Orders".parse()?;
+    ///
+    ///     let (_connection, changes) = connection
+    ///         .changes_since::<(i32, Option<String>)>(&orders, 0)
+    ///         .await?;
+    ///
+    ///     for Change { operation, row, .. } in changes {
+    ///         match operation {
+    ///             ChangeOperation::Delete => println!("delete {}", row.0),
+    ///             _ => println!("upsert {} {:?}", row.0, row.1),
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn changes_since<'a, T>(
+        self,
+        table: &'a SchemaIdent,
+        version: i64,
+    ) -> LocalBoxFuture<'a, Result<(Self, Vec<Change<T>>)>>
+    where
+        T: FromRow + 'a,
+    {
+        Box::pin(async move {
+            let sql = r#"
+                SELECT c.name, CAST(CASE WHEN ic.column_id IS NULL THEN 0 ELSE 1 END AS BIT)
+                FROM sys.columns c
+                LEFT JOIN sys.indexes i ON i.object_id = c.object_id AND i.is_primary_key = 1
+                LEFT JOIN sys.index_columns ic ON ic.object_id = i.object_id
+                    AND ic.index_id = i.index_id AND ic.column_id = c.column_id
+                WHERE c.object_id = OBJECT_ID(@p1)
+                ORDER BY c.column_id"#;
+
+            let object_name = table.object_name();
+            let (conn, columns): (_, Vec<(String, bool)>) =
+                self.query(sql, object_name.clone()).await?;
+
+            if columns.is_empty() {
+                return Err(Error::String(format!("Table `{}` not found.", table)));
+            }
+
+            if !columns.iter().any(|(_, key)| *key) {
+                return Err(Error::String(format!("Table `{}` has no primary key.", table)));
+            }
+
+            let n = columns.len();
+            let sql = changes_sql(table, &columns);
+
+            conn.query_map(sql, (version, object_name), move |row| {
+                let operation: &str = row.get(n + 1)?;
+
+                Ok(Change {
+                    version: row.get(n)?,
+                    operation: match operation {
+                        "I" => ChangeOperation::Insert,
+                        "U" => ChangeOperation::Update,
+                        "D" => ChangeOperation::Delete,
+                        s => return Err(Error::String(format!("Unknown change `{}`.", s))),
+                    },
+                    row: T::from_row(row)?,
+                })
+            })
+            .await
+        })
+    }
+}
+
+/// Selects the columns of the table, the keys being read from the change table so that they
+/// are set for the deleted rows, followed by the version and the operation.
+fn changes_sql(table: &SchemaIdent, columns: &[(String, bool)]) -> String {
+    let select = columns
+        .iter()
+        .map(|(c, key)| format!("{}.{}", if *key { "ct" } else { "t" }, quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let on = columns
+        .iter()
+        .filter(|(_, key)| *key)
+        .map(|(c, _)| format!("t.{0} = ct.{0}", quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    format!(
+        r#"
+        IF @p1 < CHANGE_TRACKING_MIN_VALID_VERSION(OBJECT_ID(@p2))
+            THROW 50000, N'The version is older than the minimum valid version.', 1;
+
+        SELECT {}, ct.SYS_CHANGE_VERSION, ct.SYS_CHANGE_OPERATION
+        FROM CHANGETABLE(CHANGES {}, @p1) AS ct
+        LEFT JOIN {1} AS t ON {}
+        ORDER BY ct.SYS_CHANGE_VERSION"#,
+        select, table, on
+    )
+}
+
+#[test]
+fn changes_sql_works() {
+    let table = "dbo.Orders".parse().unwrap();
+    let columns = [("Id".to_owned(), true), ("Name".to_owned(), false)];
+    let sql = changes_sql(&table, &columns);
+
+    assert!(sql.contains("SELECT ct.[Id], t.[Name], ct.SYS_CHANGE_VERSION"));
+    assert!(sql.contains("FROM CHANGETABLE(CHANGES [dbo].[Orders], @p1) AS ct"));
+    assert!(sql.contains("LEFT JOIN [dbo].[Orders] AS t ON t.[Id] = ct.[Id]"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn change_tracking_current_version() -> Result<()> {
+        let connection = Connection::from_env("MSSQL_DB").await?;
+        let (_, version) = connection.change_tracking_current_version().await?;
+
+        assert!(version.map_or(true, |v| v >= 0));
+        Ok(())
+    }
+}
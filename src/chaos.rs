@@ -0,0 +1,150 @@
+use crate::Result;
+use futures_timer::Delay;
+use std::{
+    io::{self, ErrorKind},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Artificial latency and faults injected before the statements of a connection,
+/// to exercise retry and reconnect code paths in integration tests.
+///
+/// Each rate is a probability between `0.0` and `1.0` checked before every statement.
+/// A dropped connection or a transient error is returned as an
+/// [Error::Io](error/enum.Error.html#variant.Io) and consumes the connection,
+/// like a real network failure would.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Chaos, ConnectionFactory, Result};
+/// use std::time::Duration;
+///
+/// fn main() -> Result<()> {
+///     let chaos = Chaos::new()
+///         .delay(0.2, Duration::from_millis(500))
+///         .drop_connection(0.01)
+///         .transient_error(0.05);
+///
+///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?.with_chaos(chaos);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Chaos {
+    delay: Option<(f64, Duration)>,
+    drop_rate: f64,
+    error_rate: f64,
+    state: AtomicU64,
+}
+
+impl Chaos {
+    /// Creates a `Chaos` that injects nothing, seeded from the clock.
+    pub fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Self {
+            delay: None,
+            drop_rate: 0.0,
+            error_rate: 0.0,
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Sets the seed of the random generator, to replay the same sequence of faults.
+    pub fn seed(self, seed: u64) -> Self {
+        self.state.store(seed, Ordering::Relaxed);
+        self
+    }
+
+    /// Waits `delay` before sending a statement, at the given rate.
+    pub fn delay(mut self, rate: f64, delay: Duration) -> Self {
+        self.delay = Some((rate, delay));
+        self
+    }
+
+    /// Drops the connection instead of sending a statement, at the given rate.
+    pub fn drop_connection(mut self, rate: f64) -> Self {
+        self.drop_rate = rate;
+        self
+    }
+
+    /// Fails a statement with a timeout instead of sending it, at the given rate.
+    pub fn transient_error(mut self, rate: f64) -> Self {
+        self.error_rate = rate;
+        self
+    }
+
+    pub(crate) async fn inject(&self) -> Result<()> {
+        if let Some((rate, delay)) = self.delay {
+            if self.hit(rate) {
+                tracing::debug!("chaos: delaying for {:?}", delay);
+                Delay::new(delay).await;
+            }
+        }
+
+        if self.hit(self.drop_rate) {
+            tracing::debug!("chaos: dropping connection");
+            let e = io::Error::new(ErrorKind::ConnectionReset, "chaos: connection dropped");
+            return Err(e.into());
+        }
+
+        if self.hit(self.error_rate) {
+            tracing::debug!("chaos: transient error");
+            let e = io::Error::new(ErrorKind::TimedOut, "chaos: transient error");
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    fn hit(&self, rate: f64) -> bool {
+        rate > 0.0 && self.next_f64() < rate
+    }
+
+    /// splitmix64, good enough to spread faults without a dependency.
+    fn next_f64(&self) -> f64 {
+        const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+        let mut z = self
+            .state
+            .fetch_add(GAMMA, Ordering::Relaxed)
+            .wrapping_add(GAMMA);
+
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn chaos_works() {
+    use crate::Error;
+    use futures03::executor::block_on;
+
+    assert!(block_on(Chaos::new().inject()).is_ok());
+
+    match block_on(Chaos::new().transient_error(1.0).inject()) {
+        Err(Error::Io(e)) => assert_eq!(ErrorKind::TimedOut, e.kind()),
+        _ => panic!("transient error expected"),
+    }
+
+    match block_on(Chaos::new().drop_connection(1.0).inject()) {
+        Err(Error::Io(e)) => assert_eq!(ErrorKind::ConnectionReset, e.kind()),
+        _ => panic!("dropped connection expected"),
+    }
+
+    let a = Chaos::new().seed(42);
+    let b = Chaos::new().seed(42);
+    assert!((0..100).all(|_| a.next_f64() == b.next_f64()));
+}
@@ -1,4 +1,4 @@
-use crate::{FromRow, Params, Result, Row};
+use crate::{Error, FromColumn, FromRow, Params, Result, Row};
 use futures03::future::LocalBoxFuture;
 use std::{borrow::Cow, fmt::Debug};
 
@@ -23,6 +23,36 @@ pub trait Command {
         S: Debug + Into<Cow<'static, str>> + 'a,
         Self: Sized;
 
+    /// Execute a sql statement with an `OUTPUT` clause and reads the output rows.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Command, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let conn = Connection::from_env("MSSQL_DB").await?;
+    ///     let conn = Command::execute(conn, "CREATE TABLE #T (Id INT IDENTITY, V INT)", ()).await?;
+    ///     let (_, ids) = Command::execute_returning(conn, "INSERT #T (V) OUTPUT inserted.Id VALUES (@p1)", 10).await?;
+    ///
+    ///     assert_eq!(1, ids[0]);
+    ///     Ok(())
+    /// }
+    /// ```
+    fn execute_returning<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, Vec<T>)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        Self: Sized,
+        T: FromRow + 'a,
+    {
+        self.query(sql, params)
+    }
+
     /// Query the database and reads all rows.
     ///
     /// # Example
@@ -104,6 +134,154 @@ pub trait Command {
         S: Debug + Into<Cow<'static, str>> + 'a,
         Self: Sized,
         T: 'a;
+
+    /// Query the database and reads the only row, failing with
+    /// [Error::NoRows](enum.Error.html#variant.NoRows) if there is none and
+    /// [Error::TooManyRows](enum.Error.html#variant.TooManyRows) if there are more.
+    ///
+    /// **Like any error, `NoRows` and `TooManyRows` consume the command:** the connection is
+    /// dropped and a transaction rolled back, although they are still usable. Use
+    /// [query_opt](#method.query_opt) to keep the command when there is no row, or a
+    /// [Session](struct.Session.html), which keeps it on both errors.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Command, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let conn = Connection::from_env("MSSQL_DB").await?;
+    ///     let (_, (id, _name)): (_, (i32, String)) =
+    ///         Command::query_one(conn, "SELECT @p1, N'Foo'", 10).await?;
+    ///
+    ///     assert_eq!(10, id);
+    ///     Ok(())
+    /// }
+    /// ```
+    fn query_one<'a, T, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        Self: Sized + 'a,
+        T: FromRow + 'a,
+    {
+        let query = query_first(self, sql, params);
+
+        Box::pin(async move {
+            match query.await? {
+                (_, _, count) if count > 1 => Err(Error::TooManyRows(count)),
+                (c, Some(v), _) => Ok((c, v)),
+                (_, None, _) => Err(Error::NoRows),
+            }
+        })
+    }
+
+    /// Query the database and reads the row if there is one, failing with
+    /// [Error::TooManyRows](enum.Error.html#variant.TooManyRows) if there are more.
+    ///
+    /// Only the first row is converted. **`TooManyRows` drops the command** as explained in
+    /// [query_one](#method.query_one).
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Command, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let conn = Connection::from_env("MSSQL_DB").await?;
+    ///     let (_, row): (_, Option<i32>) =
+    ///         Command::query_opt(conn, "SELECT 1 WHERE 1 = @p1", 0).await?;
+    ///
+    ///     assert_eq!(None, row);
+    ///     Ok(())
+    /// }
+    /// ```
+    fn query_opt<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, Option<T>)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        Self: Sized + 'a,
+        T: FromRow + 'a,
+    {
+        let query = query_first(self, sql, params);
+
+        Box::pin(async move {
+            match query.await? {
+                (_, _, count) if count > 1 => Err(Error::TooManyRows(count)),
+                (c, first, _) => Ok((c, first)),
+            }
+        })
+    }
+
+    /// Query the database and reads the first column of the first row, failing with
+    /// [Error::NoRows](enum.Error.html#variant.NoRows) if there is no row.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Command, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let conn = Connection::from_env("MSSQL_DB").await?;
+    ///     let (_, count): (_, i32) =
+    ///         Command::query_scalar(conn, "SELECT COUNT(*) FROM sys.objects", ()).await?;
+    ///
+    ///     assert!(count > 0);
+    ///     Ok(())
+    /// }
+    /// ```
+    fn query_scalar<'a, T, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        Self: Sized + 'a,
+        T: for<'b> FromColumn<'b> + 'a,
+    {
+        let query = self.query_fold(sql, params, None, |first, row| {
+            Ok(match first {
+                None => Some(row.get(0)?),
+                first => first,
+            })
+        });
+
+        Box::pin(async move {
+            match query.await? {
+                (c, Some(v)) => Ok((c, v)),
+                (_, None) => Err(Error::NoRows),
+            }
+        })
+    }
+}
+
+/// Reads the first row and counts the rows, keeping the command whatever their number.
+pub(crate) fn query_first<'a, C, T, S, P>(
+    command: C,
+    sql: S,
+    params: P,
+) -> LocalBoxFuture<'a, Result<(C, Option<T>, u64)>>
+where
+    C: Command + 'a,
+    P: Debug + Params<'a> + 'a,
+    S: Debug + Into<Cow<'static, str>> + 'a,
+    T: FromRow + 'a,
+{
+    let query = command.query_fold(sql, params, (None, 0u64), |(first, count), row| {
+        let first = match first {
+            None => Some(T::from_row(row)?),
+            first => first,
+        };
+
+        Ok((first, count + 1))
+    });
+
+    Box::pin(async move {
+        let (c, (first, count)) = query.await?;
+        Ok((c, first, count))
+    })
 }
 
 #[cfg(test)]
@@ -131,4 +309,37 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn query_one_opt() -> Result<()> {
+        let sql = "SELECT v FROM (VALUES (1), (2)) t(v) WHERE v >= @p1";
+        let conn = Connection::from_env("MSSQL_DB").await?;
+
+        let (conn, v): (_, i32) = Command::query_one(conn, sql, 2).await?;
+        assert_eq!(2, v);
+
+        let (conn, v): (_, Option<i32>) = Command::query_opt(conn, sql, 3).await?;
+        assert_eq!(None, v);
+
+        let r: Result<(_, Option<i32>)> = Command::query_opt(conn, sql, 1).await;
+        assert!(matches!(r, Err(Error::TooManyRows(2))));
+
+        let conn = Connection::from_env("MSSQL_DB").await?;
+        let r: Result<(_, i32)> = Command::query_one(conn, sql, 3).await;
+        assert!(matches!(r, Err(Error::NoRows)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_scalar() -> Result<()> {
+        let sql = "SELECT v, 0 FROM (VALUES (1), (2)) t(v) WHERE v >= @p1 ORDER BY v";
+        let conn = Connection::from_env("MSSQL_DB").await?;
+
+        let (conn, v): (_, i32) = Command::query_scalar(conn, sql, 1).await?;
+        assert_eq!(1, v);
+
+        let r: Result<(_, i32)> = Command::query_scalar(conn, sql, 3).await;
+        assert!(matches!(r, Err(Error::NoRows)));
+        Ok(())
+    }
 }
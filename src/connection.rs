@@ -1,12 +1,27 @@
 use crate::{
-    utils::{adjust_conn_str, params_to_vec, reduce},
-    Command, FromRow, Params, Result, Row, Transaction,
+    context::Context,
+    utils::{adjust_conn_str, batch_sql, params_to_vec, reduce, reduce_async, unblock},
+    Command, Error, ExecutionMode, FromColumn, FromRow, Ident, IdlePolicy, LengthPolicy,
+    Parameter, Params, QueryObserver, Result, Row, SchemaIdent, Transaction, TransactionOptions,
 };
-use futures03::{compat::Future01CompatExt, future::LocalBoxFuture};
+use futures03::{
+    compat::Future01CompatExt,
+    future::{ready, select, Either, LocalBoxFuture, TryFutureExt},
+};
+use futures_timer::Delay;
 use futures_state_stream::StateStream;
-use std::{borrow::Cow, env::var, ffi::OsStr, fmt::Debug};
+use std::{
+    borrow::Cow,
+    env::var,
+    ffi::OsStr,
+    fmt::Debug,
+    future::Future,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tiberius::{query::QueryRow, BoxableIo, SqlConnection};
-use tracing::instrument;
+use tracing::{field::Empty, instrument, Span};
 
 /// A database connection.
 ///
@@ -24,7 +39,10 @@ use tracing::instrument;
 ///     Ok(())
 /// }
 /// ```
-pub struct Connection(pub(super) SqlConnection<Box<dyn BoxableIo>>);
+pub struct Connection {
+    pub(crate) inner: SqlConnection<Box<dyn BoxableIo>>,
+    pub(crate) ctx: Context,
+}
 
 impl Command for Connection {
     fn execute<'a, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<Self>>
@@ -70,17 +88,210 @@ impl Connection {
     where
         S: Debug + Into<String> + 'a,
     {
-        Box::pin(Self::connect_imp(conn_str))
+        Box::pin(Self::connect_imp(conn_str, Context::default()))
     }
 
-    #[instrument(level = "debug", name = "Connection::connect", err)]
-    async fn connect_imp<S>(conn_str: S) -> Result<Self>
+    #[instrument(level = "debug", name = "Connection::connect", skip(ctx), err)]
+    pub(crate) async fn connect_imp<S>(conn_str: S, mut ctx: Context) -> Result<Self>
     where
         S: Debug + Into<String>,
     {
-        let conn_str = adjust_conn_str(&conn_str.into())?;
-        let c = SqlConnection::connect(&conn_str).compat().await?;
-        Ok(Connection(c))
+        ctx.chaos().await?;
+
+        let (conn_str, resolve) = (conn_str.into(), !ctx.keep_hostname);
+        let conn_str = unblock(move || adjust_conn_str(&conn_str, resolve)).await?;
+        let connect = SqlConnection::connect(&conn_str).compat();
+
+        let inner = match ctx.connect_timeout {
+            Some(timeout) => match select(Box::pin(connect), Delay::new(timeout)).await {
+                Either::Left((r, _)) => r?,
+                Either::Right(_) => {
+                    return Err(Error::Io(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("connection timed out after {:?}", timeout),
+                    )))
+                }
+            },
+            None => connect.await?,
+        };
+
+        let mut sql = String::new();
+
+        if let Some(init) = &ctx.session_init {
+            sql.push_str(init);
+            sql.push_str(";\n");
+        }
+
+        // read after the session init so that its SET statements are reflected in @@OPTIONS
+        sql.push_str("SELECT @@SPID, DB_NAME(), CAST(SERVERPROPERTY('ProductVersion') AS NVARCHAR(128)), @@OPTIONS");
+
+        let (inner, state): (_, Option<(i16, Option<String>, String, i32)>) =
+            reduce(inner.simple_query(sql), None, |_, row| {
+                let row = Row(row);
+                Ok(Some((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            })
+            .await?;
+
+        if let Some((spid, database, server_version, options)) = state {
+            ctx.spid = spid;
+            ctx.database = database;
+            ctx.server_version = server_version;
+            ctx.options = options;
+        }
+
+        ctx.opened();
+        Ok(Connection { inner, ctx })
+    }
+
+    /// Closes the connection, ending its session on the server.
+    ///
+    /// TDS has no logout message, the session ends when the socket is closed, so this is the
+    /// same as dropping the connection, which reports a
+    /// [ConnectionEvent::Closed](enum.ConnectionEvent.html#variant.Closed).
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     connection.close();
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn close(self) {
+        drop(self);
+    }
+
+    /// Sets how this connection and its transactions send the statements with parameters,
+    /// overriding [ConnectionFactory::execution_mode](struct.ConnectionFactory.html#method.execution_mode).
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, ExecutionMode, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let (connection, v): (_, i32) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .with_execution_mode(ExecutionMode::SpExecuteSql)
+    ///         .query_scalar("SELECT @p1 + 1", 41)
+    ///         .await?;
+    ///
+    ///     assert_eq!(42, v);
+    ///     let _connection = connection.with_execution_mode(ExecutionMode::Driver);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_execution_mode(mut self, mode: ExecutionMode) -> Self {
+        self.ctx.execution_mode = mode;
+        self
+    }
+
+    /// Sets the [QueryObserver](trait.QueryObserver.html) notified of the statements
+    /// executed on this connection and its transactions.
+    pub fn with_observer<O>(mut self, observer: O) -> Self
+    where
+        O: QueryObserver + 'static,
+    {
+        self.ctx.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Injects artificial latency and faults before the statements of this connection
+    /// and its transactions.
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: crate::Chaos) -> Self {
+        self.ctx.chaos = Some(Arc::new(chaos));
+        self
+    }
+
+    /// Logs a `warn!` event with the sql and the duration of every statement
+    /// taking longer than `threshold` on this connection and its transactions.
+    pub fn slow_query_warn(mut self, threshold: Duration) -> Self {
+        self.ctx.slow_query = Some(threshold);
+        self
+    }
+
+    /// Sets the maximum number of characters of the string parameters sent by
+    /// this connection and what to do with longer values.
+    pub fn max_param_len(mut self, max: usize, policy: LengthPolicy) -> Self {
+        self.ctx.max_param_len = Some((max, policy));
+        self
+    }
+
+    /// Watches the transactions of this connection and applies the policy when a
+    /// transaction is idle for longer than `max` between two operations.
+    ///
+    /// The check is made when the transaction is used again, including on commit.
+    pub fn transaction_idle_watchdog(mut self, max: Duration, policy: IdlePolicy) -> Self {
+        self.ctx.idle_watchdog = Some((max, policy));
+        self
+    }
+
+    /// Returns the current database of the connection, if known.
+    ///
+    /// This is the database of the session at login until
+    /// [use_database](#method.use_database) is called.
+    pub fn database(&self) -> Option<&str> {
+        self.ctx.database.as_deref()
+    }
+
+    /// Returns the `@@OPTIONS` bitmask of the session, read at login.
+    ///
+    /// The `SET` options of the [session init](struct.ConnectionFactory.html#method.with_session_init)
+    /// statements are included.
+    pub fn options(&self) -> i32 {
+        self.ctx.options
+    }
+
+    /// Returns the product version of the server, like `14.0.3045.24`, read at login.
+    pub fn server_version(&self) -> &str {
+        &self.ctx.server_version
+    }
+
+    /// Returns the session id (`@@SPID`) of the connection, read at login.
+    pub fn spid(&self) -> i16 {
+        self.ctx.spid
+    }
+
+    /// Changes the current database of the connection using `USE`.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .use_database(&"tempdb".parse()?)
+    ///         .await?;
+    ///
+    ///     assert_eq!(Some("tempdb"), connection.database());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn use_database<'a>(self, database: &'a Ident) -> LocalBoxFuture<'a, Result<Self>> {
+        Box::pin(self.use_database_imp(database))
+    }
+
+    #[instrument(level = "debug", name = "Connection::use_database", skip(self), err)]
+    async fn use_database_imp(self, database: &Ident) -> Result<Self> {
+        let sql = format!("USE {}; SELECT DB_NAME()", database);
+        let (mut conn, rows): (_, Vec<String>) = self.query(sql, ()).await?;
+
+        match rows.into_iter().next() {
+            Some(name) if name.eq_ignore_ascii_case(database.as_str()) => {
+                conn.ctx.database = Some(name);
+                Ok(conn)
+            }
+            _ => Err(Error::String(format!(
+                "Could not switch to database `{}`.",
+                database
+            ))),
+        }
     }
 
     /// Creates a connection that will connect to the database specified in the environment variable.
@@ -107,6 +318,46 @@ impl Connection {
         Ok(Connection::connect(conn_str).await?)
     }
 
+    /// Returns an approximate number of rows of a table, read from the
+    /// `sys.dm_db_partition_stats` view instead of a `COUNT(*)`.
+    ///
+    /// Requires the `VIEW DATABASE STATE` permission. An error is returned if the table is not found.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let orders = "dbo.Orders".parse()?;
+    ///     let (connection, count) = connection.estimate_row_count(&orders).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn estimate_row_count<'a>(
+        self,
+        table: &'a SchemaIdent,
+    ) -> LocalBoxFuture<'a, Result<(Self, i64)>> {
+        Box::pin(self.estimate_row_count_imp(table))
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "Connection::estimate_row_count",
+        skip(self),
+        err
+    )]
+    async fn estimate_row_count_imp(self, table: &SchemaIdent) -> Result<(Self, i64)> {
+        let sql = "SELECT CAST(SUM(row_count) AS BIGINT) FROM sys.dm_db_partition_stats WHERE object_id = OBJECT_ID(@p1) AND index_id IN (0, 1)";
+        let (conn, rows): (_, Vec<Option<i64>>) = self.query(sql, table.object_name()).await?;
+
+        match rows.into_iter().next().flatten() {
+            Some(count) => Ok((conn, count)),
+            None => Err(Error::String(format!("Table `{}` not found.", table))),
+        }
+    }
+
     /// Execute sql statements that don't return rows.
     ///
     /// # Example
@@ -128,25 +379,106 @@ impl Connection {
         Box::pin(self.execute_imp(sql, params))
     }
 
-    #[instrument(level = "debug", name = "Connection::execute", skip(self), err)]
+    /// Execute many sql statements in a single round trip.
+    ///
+    /// The statements are concatenated into one batch and the `@pN` placeholders
+    /// of each statement are renumbered to match the combined parameters.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .execute("CREATE TABLE #T (Id INT, Name NVARCHAR(10))", ())
+    ///         .await?
+    ///         .execute_batch(vec![
+    ///             ("INSERT #T VALUES (@p1, @p2)", (1, "Foo")),
+    ///             ("INSERT #T VALUES (@p1, @p2)", (2, "Bar")),
+    ///         ])
+    ///         .await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn execute_batch<'a, S, P>(self, batch: Vec<(S, P)>) -> LocalBoxFuture<'a, Result<Self>>
+    where
+        P: Params<'a>,
+        S: Into<Cow<'static, str>>,
+    {
+        let (sql, params) = batch_sql(batch);
+        self.execute(sql, params)
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "Connection::execute",
+        skip(self, sql, params),
+        fields(sql = Empty, params = Empty, param_bytes = Empty, affected_rows = Empty),
+        err
+    )]
     async fn execute_imp<'a, S, P>(self, sql: S, params: P) -> Result<Self>
     where
         S: Debug + Into<Cow<'static, str>> + 'a,
         P: Debug + Params<'a> + 'a,
     {
+        let sql = sql.into();
         let mut p = Vec::new();
         params.params(&mut p);
+        let started = self.ctx.start(&sql, &mut p).await?;
+        let sql = self.ctx.prepare_sql(sql, &p);
 
-        let sql = sql.into();
-
-        let (_affected_rows, conn) = if p.is_empty() {
-            self.0.simple_exec(sql).compat().await
+        let result = if p.is_empty() {
+            self.inner.simple_exec(sql).compat().await
         } else {
             let params = params_to_vec(&p);
-            self.0.exec(sql, &params).compat().await
-        }?;
+            self.inner.exec(sql, &params).compat().await
+        }
+        .map_err(Error::from);
+
+        self.ctx.on_end(started, &result, |(affected_rows, _)| *affected_rows);
+        let (affected_rows, inner) = result?;
+        Span::current().record("affected_rows", &affected_rows);
 
-        Ok(Self(conn))
+        Ok(Self {
+            inner,
+            ctx: self.ctx,
+        })
+    }
+
+    /// Execute a sql statement with an `OUTPUT` clause and returns the output rows.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .execute("CREATE TABLE #T (Id INT IDENTITY, Name NVARCHAR(10))", ())
+    ///         .await?;
+    ///
+    ///     let (connection, ids): (_, Vec<i32>) = connection
+    ///         .execute_returning("INSERT #T (Name) OUTPUT inserted.Id VALUES (@p1)", "Foo")
+    ///         .await?;
+    ///
+    ///     assert_eq!(ids[0], 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn execute_returning<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, Vec<T>)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        self.query_map(sql, params, FromRow::from_row)
     }
 
     /// Execute sql query and returns all the rows.
@@ -176,6 +508,45 @@ impl Connection {
         self.query_map(sql, params, FromRow::from_row).await
     }
 
+    /// Executes an sql command with an owned sql and owned parameters, returning a `'static`
+    /// future that can be moved into a task, with `tokio::task::spawn_local` for example.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Params, Parameter, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let mut params: Vec<Parameter<'static>> = Vec::new();
+    ///     "owned".to_owned().params(&mut params);
+    ///
+    ///     let future = connection.execute_owned("DECLARE @v NVARCHAR(10) = @p1".into(), params);
+    ///     let _connection = future.await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn execute_owned(
+        self,
+        sql: String,
+        params: Vec<Parameter<'static>>,
+    ) -> LocalBoxFuture<'static, Result<Self>> {
+        self.execute(sql, params)
+    }
+
+    /// Queries the database with an owned sql and owned parameters, returning a `'static`
+    /// future, see [execute_owned](#method.execute_owned).
+    pub fn query_owned<T>(
+        self,
+        sql: String,
+        params: Vec<Parameter<'static>>,
+    ) -> LocalBoxFuture<'static, Result<(Self, Vec<T>)>>
+    where
+        T: FromRow + 'static,
+    {
+        Box::pin(self.query(sql, params))
+    }
+
     pub fn query_fold<'a, T, S, P, F>(
         self,
         sql: S,
@@ -192,13 +563,82 @@ impl Connection {
         Box::pin(self.query_fold_imp(sql, params, init, func))
     }
 
+    /// Query the database and reduces the rows using an async function, awaited before
+    /// reading the next row.
+    ///
+    /// The rows are read from the network as the function completes, so a slow function
+    /// applies backpressure to the query. If the function fails, the remaining rows are
+    /// read and discarded before the error is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use futures03::{channel::mpsc, SinkExt, StreamExt};
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let (tx, rx) = mpsc::channel::<i32>(1);
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let sql = "SELECT v FROM (VALUES (1), (2), (3)) t(v)";
+    ///
+    ///     // the closure holding the sender is dropped when the query completes
+    ///     let query = connection.query_fold_async(sql, (), (), move |(), row| {
+    ///         let mut tx = tx.clone();
+    ///
+    ///         async move {
+    ///             tx.send(row.get(0)?).await.map_err(|_| "receiver dropped")?;
+    ///             Ok(())
+    ///         }
+    ///     });
+    ///
+    ///     let (result, values) = futures03::join!(query, rx.collect::<Vec<_>>());
+    ///     result?;
+    ///
+    ///     assert_eq!(vec![1, 2, 3], values);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_fold_async<'a, T, S, P, F, Fut>(
+        self,
+        sql: S,
+        params: P,
+        init: T,
+        func: F,
+    ) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        F: FnMut(T, Row) -> Fut + 'a,
+        Fut: Future<Output = Result<T>> + 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: 'a,
+    {
+        Box::pin(self.query_fold_async_imp(sql, params, init, func))
+    }
+
+    pub async fn query_fold_imp<'a, T, S, P, F>(
+        self,
+        sql: S,
+        params: P,
+        init: T,
+        mut func: F,
+    ) -> Result<(Self, T)>
+    where
+        F: FnMut(T, &Row) -> Result<T>,
+        P: Debug + Params<'a>,
+        S: Debug + Into<Cow<'static, str>>,
+    {
+        self.query_fold_async_imp(sql, params, init, move |acc, row| ready(func(acc, &row)))
+            .await
+    }
+
     #[instrument(
         level = "debug",
         name = "Connection::query_fold",
-        skip(self, init, func),
+        skip(self, sql, params, init, func),
+        fields(sql = Empty, params = Empty, param_bytes = Empty, rows = Empty),
         err
     )]
-    pub async fn query_fold_imp<'a, T, S, P, F>(
+    async fn query_fold_async_imp<'a, T, S, P, F, Fut>(
         self,
         sql: S,
         params: P,
@@ -206,15 +646,23 @@ impl Connection {
         mut func: F,
     ) -> Result<(Self, T)>
     where
-        F: FnMut(T, &Row) -> Result<T>,
+        F: FnMut(T, Row) -> Fut,
+        Fut: Future<Output = Result<T>>,
         P: Debug + Params<'a>,
         S: Debug + Into<Cow<'static, str>>,
     {
+        let sql = sql.into();
         let mut p = Vec::new();
         params.params(&mut p);
+        let started = self.ctx.start(&sql, &mut p).await?;
+        let sql = self.ctx.prepare_sql(sql, &p);
+        let mut count = 0;
 
-        let sql = sql.into();
-        let next = move |r, row| func(r, &Row(row));
+        let next = |r, row| {
+            let index = count;
+            count += 1;
+            func(r, Row(row)).map_err(move |e: Error| e.at_row(index))
+        };
 
         let stream: Box<
             dyn StateStream<
@@ -223,14 +671,24 @@ impl Connection {
                 Error = tiberius::Error,
             >,
         > = if p.is_empty() {
-            Box::new(self.0.simple_query(sql))
+            Box::new(self.inner.simple_query(sql))
         } else {
-            Box::new(self.0.query(sql, &params_to_vec(&p)))
+            Box::new(self.inner.query(sql, &params_to_vec(&p)))
         };
 
-        let (conn, rows) = reduce(stream, init, next).await?;
+        let result = reduce_async(stream, init, next).await;
+        self.ctx.on_end(started, &result, |_| count);
+        Span::current().record("rows", &count);
 
-        Ok((Self(conn), rows))
+        let (inner, rows) = result?;
+
+        Ok((
+            Self {
+                inner,
+                ctx: self.ctx,
+            },
+            rows,
+        ))
     }
 
     pub fn query_map<'a, T, S, P, F>(
@@ -251,23 +709,104 @@ impl Connection {
         })
     }
 
+    /// Execute sql query and returns the only row, failing if there is none or more.
+    ///
+    /// See [Command::query_one](trait.Command.html#method.query_one).
+    pub fn query_one<'a, T, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        Command::query_one(self, sql, params)
+    }
+
+    /// Execute sql query and returns the row if there is one, failing if there are more.
+    ///
+    /// See [Command::query_opt](trait.Command.html#method.query_opt).
+    pub fn query_opt<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, Option<T>)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        Command::query_opt(self, sql, params)
+    }
+
+    /// Execute sql query and returns the first column of the first row, failing if there
+    /// is no row.
+    ///
+    /// See [Command::query_scalar](trait.Command.html#method.query_scalar).
+    pub fn query_scalar<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: for<'b> FromColumn<'b> + 'a,
+    {
+        Command::query_scalar(self, sql, params)
+    }
+
     pub fn transaction(self) -> LocalBoxFuture<'static, Result<Transaction>> {
-        Box::pin(self.transaction_imp())
+        Box::pin(self.transaction_imp(TransactionOptions::new()))
+    }
+
+    /// Starts a transaction with options, such as a name and a log mark or a distributed
+    /// transaction.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result, TransactionOptions};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let options = TransactionOptions::new()
+    ///         .name("MonthEnd")
+    ///         .mark("Month end closing");
+    ///
+    ///     let transaction = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .transaction_with(options)
+    ///         .await?;
+    ///
+    ///     transaction.commit().await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn transaction_with(
+        self,
+        options: TransactionOptions,
+    ) -> LocalBoxFuture<'static, Result<Transaction>> {
+        Box::pin(self.transaction_imp(options))
     }
 
     #[instrument(level = "debug", name = "Connection::transaction", skip(self), err)]
-    async fn transaction_imp(self) -> Result<Transaction> {
+    async fn transaction_imp(self, options: TransactionOptions) -> Result<Transaction> {
         use futures::future::Future;
 
-        let (_, t) = self
-            .0
+        let begin = options.begin_sql()?;
+
+        let (_, inner) = self
+            .inner
             .transaction()
             .and_then(|t| t.simple_exec("set implicit_transactions off"))
-            .and_then(|(_, t)| t.simple_exec("BEGIN TRANSACTION"))
+            .and_then(move |(_, t)| t.simple_exec(begin))
             .compat()
             .await?;
 
-        Ok(Transaction(t))
+        Ok(Transaction {
+            inner,
+            ctx: self.ctx,
+            depth: 0,
+            last_activity: Instant::now(),
+        })
     }
 }
 
@@ -281,6 +820,119 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn row_conversion() -> Result<()> {
+        let conn = Connection::from_env("MSSQL_DB").await?;
+        let sql = "SELECT v FROM (VALUES (1), (2), (NULL)) t(v)";
+        let r: Result<(_, Vec<i32>)> = conn.query(sql, ()).await;
+
+        assert!(matches!(r, Err(Error::RowConversion { row: 2, .. })));
+
+        let conn = Connection::from_env("MSSQL_DB").await?;
+        let r = conn
+            .query_map(sql, (), |row| match row.get::<Option<i32>>(0)? {
+                Some(v) => Ok(v),
+                None => Err(Error::Str("missing")),
+            })
+            .await;
+
+        assert!(matches!(r, Err(Error::RowConversion { row: 2, .. })));
+
+        let conn = Connection::from_env("MSSQL_DB").await?;
+        let r = conn
+            .query_fold_async(sql, (), 0, |acc, _| async move {
+                match acc {
+                    1 => Err(Error::Str("second")),
+                    acc => Ok(acc + 1),
+                }
+            })
+            .await;
+
+        assert!(matches!(r, Err(Error::RowConversion { row: 1, .. })));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn column_type_mismatch() -> Result<()> {
+        let (_, r) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query_fold("SELECT CAST(1 AS BIGINT), GETDATE()", (), None, |_, row| {
+                Ok(Some((row.get::<i32>(0), row.get::<i32>(1))))
+            })
+            .await?;
+
+        let (bigint, datetime) = r.unwrap();
+
+        assert!(matches!(
+            bigint,
+            Err(Error::ColumnTypeMismatch {
+                index: 0,
+                decoded_as: "bigint",
+                rust_type: "i32",
+                ..
+            })
+        ));
+
+        // a datetime is decoded by the driver as a datetime2
+        assert!(matches!(
+            datetime,
+            Err(Error::ColumnTypeMismatch {
+                index: 1,
+                decoded_as: "datetime2",
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unexpected_null() -> Result<()> {
+        let (_, r) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query_fold("SELECT CAST(NULL AS INT)", (), None, |_, row| {
+                Ok(Some(row.get::<i32>(0)))
+            })
+            .await?;
+
+        assert!(matches!(
+            r,
+            Some(Err(Error::UnexpectedNull {
+                index: 0,
+                column_name: None
+            }))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn execution_mode() -> Result<()> {
+        let connection = Connection::from_env("MSSQL_DB")
+            .await?
+            .with_execution_mode(ExecutionMode::SpExecuteSql)
+            .execute("CREATE TABLE #T (V NVARCHAR(10))", ())
+            .await?
+            .execute("INSERT #T VALUES (@p1), (@p2)", ("it's", "b"))
+            .await?;
+
+        let (_, rows): (_, Vec<String>) = connection
+            .query("SELECT V FROM #T WHERE V <> @p1 ORDER BY V", "")
+            .await?;
+
+        assert_eq!(vec!["b", "it's"], rows);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn estimate_row_count_not_found() -> Result<()> {
+        let result = Connection::from_env("MSSQL_DB")
+            .await?
+            .estimate_row_count(&"dbo.TableThatDoesNotExist".parse()?)
+            .await;
+
+        assert!(result.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn execute() -> Result<()> {
         Connection::from_env("MSSQL_DB")
@@ -290,6 +942,24 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn execute_batch() -> Result<()> {
+        let (_connection, rows): (_, Vec<(i32, String)>) = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #T (Id INT, Name NVARCHAR(10))", ())
+            .await?
+            .execute_batch(vec![
+                ("INSERT #T VALUES (@p1, @p2)", (1, "Foo")),
+                ("INSERT #T VALUES (@p1, @p2)", (2, "Bar")),
+            ])
+            .await?
+            .query("SELECT Id, Name FROM #T ORDER BY Id", ())
+            .await?;
+
+        assert_eq!((2, "Bar".to_owned()), rows[1]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn execute_params() -> Result<()> {
         Connection::from_env("MSSQL_DB")
@@ -299,6 +969,48 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn execute_returning() -> Result<()> {
+        let (_connection, rows): (_, Vec<(i32, String)>) = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #T (Id INT IDENTITY, Name NVARCHAR(10))", ())
+            .await?
+            .execute_returning(
+                "INSERT #T (Name) OUTPUT inserted.Id, inserted.Name VALUES (@p1), (@p2)",
+                ("Foo", "Bar"),
+            )
+            .await?;
+
+        assert_eq!(2, rows.len());
+        assert_eq!((1, "Foo".to_owned()), rows[0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn observer() -> Result<()> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        #[derive(Clone, Default)]
+        struct Rows(Arc<AtomicU64>);
+
+        impl QueryObserver for Rows {
+            fn on_complete(&self, _duration: Duration, rows: u64) {
+                self.0.fetch_add(rows, Ordering::SeqCst);
+            }
+        }
+
+        let rows = Rows::default();
+
+        Connection::from_env("MSSQL_DB")
+            .await?
+            .with_observer(rows.clone())
+            .query::<i32, _, _>("SELECT 1 UNION ALL SELECT 2", ())
+            .await?;
+
+        assert_eq!(2, rows.0.load(Ordering::SeqCst));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn query() -> Result<()> {
         let (_connection, rows) = Connection::from_env("MSSQL_DB")
@@ -310,6 +1022,28 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn query_fold_async() -> Result<()> {
+        let sql = "SELECT v FROM (VALUES (1), (2), (3)) t(v)";
+        let connection = Connection::from_env("MSSQL_DB").await?;
+
+        let (connection, sum) = connection
+            .query_fold_async(sql, (), 0, |sum, row| async move {
+                let v: i32 = row.get(0)?;
+                Ok(sum + v)
+            })
+            .await?;
+
+        assert_eq!(6, sum);
+
+        let r = connection
+            .query_fold_async(sql, (), 0, |_, _| async { Err::<i32, _>(Error::Str("failed")) })
+            .await;
+
+        assert!(r.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn query_params() -> Result<()> {
         let (_connection, rows) = Connection::from_env("MSSQL_DB")
@@ -368,4 +1102,49 @@ mod tests {
         assert_eq!(15337032f64, rows[0]);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn query_widening() -> Result<()> {
+        let (_connection, rows): (_, Vec<(i64, i32, i64)>) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query("SELECT CAST(1 AS INT), CAST(2 AS TINYINT), CAST(3 AS SMALLINT)", ())
+            .await?;
+
+        assert_eq!((1, 2, 3), rows[0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_tinyint_unsigned() -> Result<()> {
+        let sql = "SELECT CAST(200 AS TINYINT), CAST(200 AS TINYINT), CAST(200 AS TINYINT), \
+                   CAST(200 AS TINYINT), CAST(200 AS TINYINT)";
+
+        let (_connection, rows): (_, Vec<(u8, i16, i32, i64, Option<i32>)>) =
+            Connection::from_env("MSSQL_DB").await?.query(sql, ()).await?;
+
+        assert_eq!((200, 200, 200, 200, Some(200)), rows[0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn session_state() -> Result<()> {
+        let connection = Connection::from_env("MSSQL_DB").await?;
+        let (connection, spid): (_, Vec<i16>) = connection.query("SELECT @@SPID", ()).await?;
+
+        assert_eq!(spid[0], connection.spid());
+        assert!(connection.database().is_some());
+        assert!(!connection.server_version().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn use_database() -> Result<()> {
+        let connection = Connection::from_env("MSSQL_DB")
+            .await?
+            .use_database(&"tempdb".parse()?)
+            .await?;
+
+        assert_eq!(Some("tempdb"), connection.database());
+        Ok(())
+    }
 }
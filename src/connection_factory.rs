@@ -1,9 +1,25 @@
-use crate::{Connection, Result};
-use std::{ffi::OsStr, future::Future};
+use crate::{
+    context::Context, Connection, ConnectionEvent, ExecutionMode, Health, IdlePolicy, LengthPolicy,
+    QueryObserver, Result, Transaction,
+};
+use futures03::future::try_join_all;
+use futures_timer::Delay;
+use std::{
+    ffi::OsStr,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing::instrument;
+use uuid::Uuid;
 
 /// Creates a database [Connection](struct.Connection.html) on demand.
 #[derive(Clone)]
-pub struct ConnectionFactory(String);
+pub struct ConnectionFactory {
+    pub(crate) conn_str: String,
+    pub(crate) ctx: Context,
+    health: Arc<Mutex<Health>>,
+}
 
 impl ConnectionFactory {
     /// Creates a new instance.
@@ -29,7 +45,11 @@ impl ConnectionFactory {
     where
         S: Into<String>,
     {
-        ConnectionFactory(s.into())
+        ConnectionFactory {
+            conn_str: s.into(),
+            ctx: Context::default(),
+            health: Default::default(),
+        }
     }
 
     /// Create a new instance based on an environment variable.
@@ -79,7 +99,434 @@ impl ConnectionFactory {
     /// }
     /// ```
     pub fn create_connection(&self) -> impl Future<Output = Result<Connection>> {
-        Connection::connect(self.0.clone())
+        let connect = Connection::connect_imp(self.conn_str.clone(), self.ctx.clone());
+        let health = self.health.clone();
+
+        async move {
+            let result = connect.await;
+            health.lock().expect("health").record(&result);
+            result
+        }
+    }
+
+    /// Returns the outcome of the recent connections created by this factory and its clones,
+    /// for readiness probes.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?;
+    ///     connection_factory.probe(2).await?;
+    ///
+    ///     assert!(connection_factory.health().is_healthy());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn health(&self) -> Health {
+        self.health.lock().expect("health").clone()
+    }
+
+    /// Opens `n` connections in parallel and closes them, to validate the connection
+    /// string, resolve the server and report failures at application startup.
+    ///
+    /// This is a health probe: the factory has no pool, so the connections are not kept for
+    /// later use. The first error is returned.
+    #[instrument(level = "debug", name = "ConnectionFactory::probe", skip(self), err)]
+    pub async fn probe(&self, n: usize) -> Result<()> {
+        let connections = try_join_all((0..n).map(|_| self.create_connection())).await?;
+        connections.into_iter().for_each(Connection::close);
+        Ok(())
+    }
+
+    /// Runs `func` in a transaction on a new connection, committing if it succeeds.
+    ///
+    /// When the transaction fails on a deadlock (error 1205) or a snapshot isolation update
+    /// conflict (error 3960), `func` is run again from the start on a new connection, up to
+    /// `max_retries` times, after a random delay growing with the retries so that the
+    /// transactions in conflict do not run again at the same time. On other errors, the
+    /// transaction is dropped with its connection and the server rolls it back.
+    ///
+    /// `func` must not have side effects outside of the transaction, since it may run
+    /// many times.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?;
+    ///
+    ///     let (_connection, id) = connection_factory
+    ///         .run_transaction(3, |tx| async move {
+    ///             let (tx, id) = tx.query_scalar("SELECT 1", ()).await?;
+    ///             Ok((tx, id))
+    ///         })
+    ///         .await?;
+    ///
+    ///     assert_eq!(1, id);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(
+        level = "debug",
+        name = "ConnectionFactory::run_transaction",
+        skip(self, func),
+        err
+    )]
+    pub async fn run_transaction<T, F, Fut>(
+        &self,
+        max_retries: u32,
+        mut func: F,
+    ) -> Result<(Connection, T)>
+    where
+        F: FnMut(Transaction) -> Fut,
+        Fut: Future<Output = Result<(Transaction, T)>>,
+    {
+        let mut retries = 0;
+
+        loop {
+            let connection = self.create_connection().await?;
+
+            match Transaction::scope(connection, |tx| func(tx)).await {
+                Err(e) if retries < max_retries && e.is_transient_conflict() => {
+                    retries += 1;
+
+                    let delay = retry_delay(retries, Uuid::new_v4().as_u128() as u64);
+                    let (n, max) = (retries, max_retries);
+                    tracing::warn!("transaction retried ({}/{}) in {:?}: {}", n, max, delay, e);
+
+                    Delay::new(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sets the [QueryObserver](trait.QueryObserver.html) of the connections created by this factory.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, QueryObserver, Result};
+    /// use std::time::Duration;
+    ///
+    /// struct Metrics;
+    ///
+    /// impl QueryObserver for Metrics {
+    ///     fn on_complete(&self, duration: Duration, _rows: u64) {
+    ///         println!("query took {:?}", duration);
+    ///     }
+    /// }
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?.with_observer(Metrics);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_observer<O>(mut self, observer: O) -> Self
+    where
+        O: QueryObserver + 'static,
+    {
+        self.ctx.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Sets a callback called when a connection created by this factory is opened, closed
+    /// or broken by an error, to count the live connections or to wait for them to drain
+    /// during a shutdown.
+    ///
+    /// Every opened connection is reported closed once, when it is closed or dropped, such as
+    /// after an error; a broken connection is reported before it is closed.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionEvent, ConnectionFactory, Result};
+    /// use std::sync::{
+    ///     atomic::{AtomicIsize, Ordering},
+    ///     Arc,
+    /// };
+    ///
+    /// fn main() -> Result<()> {
+    ///     let live = Arc::new(AtomicIsize::new(0));
+    ///     let counter = live.clone();
+    ///
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .on_connection_event(move |event| match event {
+    ///             ConnectionEvent::Opened { .. } => {
+    ///                 counter.fetch_add(1, Ordering::SeqCst);
+    ///             }
+    ///             ConnectionEvent::Closed { .. } => {
+    ///                 counter.fetch_sub(1, Ordering::SeqCst);
+    ///             }
+    ///             ConnectionEvent::Broken { .. } => {}
+    ///         });
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_connection_event<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ConnectionEvent) + Send + Sync + 'static,
+    {
+        self.ctx.on_event = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets statements executed right after connecting, so that all the connections
+    /// created by this factory use the same session settings.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .with_session_init(&["SET LOCK_TIMEOUT 5000", "SET DATEFIRST 1"]);
+    ///
+    ///     let (_, rows) = connection_factory
+    ///         .create_connection()
+    ///         .await?
+    ///         .query("SELECT @@LOCK_TIMEOUT", ())
+    ///         .await?;
+    ///
+    ///     assert_eq!(5000, rows[0]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_session_init<S>(mut self, statements: &[S]) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let sql = statements
+            .iter()
+            .map(|s| s.as_ref())
+            .collect::<Vec<_>>()
+            .join(";\n");
+
+        self.ctx.session_init = if sql.is_empty() {
+            None
+        } else {
+            Some(sql.into())
+        };
+
+        self
+    }
+
+    /// Injects artificial latency and faults when connecting and before the statements
+    /// of the connections created by this factory.
+    ///
+    /// The same [Chaos](struct.Chaos.html) is shared by all the connections.
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: crate::Chaos) -> Self {
+        self.ctx.chaos = Some(Arc::new(chaos));
+        self
+    }
+
+    /// Sets whether the host name of the data source is resolved to an ip address before
+    /// connecting, which is the default.
+    ///
+    /// Pass `false` to keep the host name up to the driver, when it must reach the server,
+    /// as with a proxy routing on the name or a DNS round-robin resolved on each connection.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory =
+    ///         ConnectionFactory::from_env("MSSQL_DB")?.resolve_hostname(false);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn resolve_hostname(mut self, resolve: bool) -> Self {
+        self.ctx.keep_hostname = !resolve;
+        self
+    }
+
+    /// Sets the maximum duration to open the socket and log in, after which the connections
+    /// created by this factory fail with an `Io` error of kind `TimedOut`, instead of waiting
+    /// for the timeout of the operating system when the server does not answer.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .connect_timeout(Duration::from_secs(15));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.ctx.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Logs a `warn!` event with the sql and the duration of every statement
+    /// taking longer than `threshold` on the connections created by this factory.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .slow_query_warn(Duration::from_secs(2));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn slow_query_warn(mut self, threshold: Duration) -> Self {
+        self.ctx.slow_query = Some(threshold);
+        self
+    }
+
+    /// Sets how the connections created by this factory send the statements with parameters.
+    ///
+    /// It can be changed for a connection with
+    /// [Connection::with_execution_mode](struct.Connection.html#method.with_execution_mode).
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, ExecutionMode, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .execution_mode(ExecutionMode::SpExecuteSql);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn execution_mode(mut self, mode: ExecutionMode) -> Self {
+        self.ctx.execution_mode = mode;
+        self
+    }
+
+    /// Records the first `max_chars` characters of the sql in the `sql` field of the
+    /// `execute` and `query` spans of the connections created by this factory.
+    ///
+    /// The sql is not recorded by default. The spans always record `param_bytes`, the
+    /// approximate size of the parameters, and `rows` or `affected_rows`.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .trace_sql_preview(200);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn trace_sql_preview(mut self, max_chars: usize) -> Self {
+        self.ctx.sql_preview = Some(max_chars);
+        self
+    }
+
+    /// Applies `redact` to the sql before it is written to the spans and the slow query
+    /// events of the connections created by this factory.
+    ///
+    /// [scrub_literals](fn.scrub_literals.html) masks the string literals. The sql given to
+    /// the [QueryObserver](trait.QueryObserver.html) is not redacted.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .trace_sql_preview(200)
+    ///         .redact_sql(|sql| sql.replace("Password", "***"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn redact_sql<F>(mut self, redact: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.ctx.redact_sql = Some(Arc::new(redact));
+        self
+    }
+
+    /// Writes the kind of the parameters, such as `String` or `I32`, instead of their values
+    /// to the spans of the connections created by this factory.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?.redact_params();
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn redact_params(mut self) -> Self {
+        self.ctx.redact_params = true;
+        self
+    }
+
+    /// Checks, before sending each statement of the connections created by this factory, that
+    /// the number of parameters is the highest `@pN` placeholder of the sql, returning an
+    /// [Error::ParameterMismatch](error/enum.Error.html#variant.ParameterMismatch) instead of
+    /// an error of the server.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?.strict_params();
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn strict_params(mut self) -> Self {
+        self.ctx.strict_params = true;
+        self
+    }
+
+    /// Sets the maximum number of characters of the string parameters sent by the
+    /// connections created by this factory and what to do with longer values.
+    ///
+    /// This catches values that would be rejected or silently truncated by narrow columns.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, LengthPolicy, Result};
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .max_param_len(4000, LengthPolicy::Error);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn max_param_len(mut self, max: usize, policy: LengthPolicy) -> Self {
+        self.ctx.max_param_len = Some((max, policy));
+        self
+    }
+
+    /// Watches the transactions of the connections created by this factory and applies
+    /// the policy when a transaction is idle for longer than `max` between two operations.
+    ///
+    /// This catches transactions left open while awaiting something else. The check is
+    /// made when the transaction is used again, including on commit.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, IdlePolicy, Result};
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> Result<()> {
+    ///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+    ///         .transaction_idle_watchdog(Duration::from_secs(30), IdlePolicy::Rollback);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn transaction_idle_watchdog(mut self, max: Duration, policy: IdlePolicy) -> Self {
+        self.ctx.idle_watchdog = Some((max, policy));
+        self
     }
 }
 
@@ -92,3 +539,200 @@ where
         ConnectionFactory::new(s)
     }
 }
+
+/// The delay before running a transaction again, a backoff doubling from 20ms up to 1s, of
+/// which the second half is random.
+fn retry_delay(retry: u32, random: u64) -> Duration {
+    let backoff = (20u64 << retry.saturating_sub(1).min(6)).min(1000);
+    let half = backoff / 2;
+
+    Duration::from_millis(half + random % (half + 1))
+}
+
+#[test]
+fn retry_delay_works() {
+    assert_eq!(Duration::from_millis(10), retry_delay(1, 0));
+    assert_eq!(Duration::from_millis(20), retry_delay(1, 10));
+    assert_eq!(Duration::from_millis(40), retry_delay(2, 20));
+    assert!((20..=40).contains(&retry_delay(2, u64::MAX).as_millis()));
+    assert_eq!(Duration::from_millis(500), retry_delay(30, 0));
+    assert!(retry_delay(30, u64::MAX) <= Duration::from_secs(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn probe() -> Result<()> {
+        let factory = ConnectionFactory::from_env("MSSQL_DB")?;
+        assert!(!factory.health().is_healthy());
+
+        factory.probe(3).await?;
+        assert!(factory.health().is_healthy());
+
+        let failing = ConnectionFactory::new("server=tcp:invalid.host.local;database=master");
+        assert!(failing.probe(1).await.is_err());
+        assert_eq!(1, failing.health().consecutive_failures);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connect_timeout() {
+        // a non-routable address, the connection attempt never completes
+        let factory = ConnectionFactory::new("server=tcp:10.255.255.1;database=master")
+            .connect_timeout(Duration::from_millis(200));
+
+        match factory.create_connection().await {
+            Err(crate::Error::Io(e)) => assert_eq!(std::io::ErrorKind::TimedOut, e.kind()),
+            r => panic!("unexpected {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn strict_params() -> Result<()> {
+        #[derive(Clone, Default)]
+        struct Errors(Arc<Mutex<Vec<String>>>);
+
+        impl QueryObserver for Errors {
+            fn on_error(&self, error: &crate::Error) {
+                self.0.lock().unwrap().push(error.to_string());
+            }
+        }
+
+        let errors = Errors::default();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+
+        let factory = ConnectionFactory::from_env("MSSQL_DB")?
+            .strict_params()
+            .with_observer(errors.clone())
+            .on_connection_event(move |e| log.lock().unwrap().push(e));
+
+        let connection = factory.create_connection().await?;
+        let spid = connection.spid();
+        let r = connection.execute("SELECT @p1, @p2", 1).await;
+        assert!(matches!(
+            r,
+            Err(crate::Error::ParameterMismatch { placeholders: 2, params: 1 })
+        ));
+
+        // the rejected statement is seen by the observer and breaks the connection
+        assert_eq!(1, errors.0.lock().unwrap().len());
+        assert_eq!(
+            Some(&ConnectionEvent::Broken { spid }),
+            events.lock().unwrap().get(1)
+        );
+
+        factory.create_connection().await?.execute("SELECT @p1, @p2", (1, 2)).await?;
+        assert_eq!(1, errors.0.lock().unwrap().len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connection_events() -> Result<()> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+
+        let factory = ConnectionFactory::from_env("MSSQL_DB")?
+            .on_connection_event(move |e| log.lock().unwrap().push(e));
+
+        factory.create_connection().await?.close();
+
+        let broken = factory.create_connection().await?;
+        let spid = broken.spid();
+        assert!(broken.execute("SELECT * FROM #Missing", ()).await.is_err());
+
+        let connection = factory.create_connection().await?;
+        let spid2 = connection.spid();
+        drop(connection.transaction().await?);
+
+        let events = events.lock().unwrap();
+        assert_eq!(7, events.len());
+        assert!(matches!(events[0], ConnectionEvent::Opened { .. }));
+        assert!(matches!(events[1], ConnectionEvent::Closed { .. }));
+        assert_eq!(ConnectionEvent::Opened { spid }, events[2]);
+        assert_eq!(ConnectionEvent::Broken { spid }, events[3]);
+        assert_eq!(ConnectionEvent::Closed { spid }, events[4]);
+        assert_eq!(ConnectionEvent::Opened { spid: spid2 }, events[5]);
+        assert_eq!(ConnectionEvent::Closed { spid: spid2 }, events[6]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_transaction() -> Result<()> {
+        let factory = ConnectionFactory::from_env("MSSQL_DB")?;
+        let mut runs = 0;
+
+        let (_, v) = factory
+            .run_transaction(2, |tx| {
+                runs += 1;
+
+                async move {
+                    let (tx, v): (_, i32) = tx.query_scalar("SELECT @@TRANCOUNT", ()).await?;
+                    Ok((tx, v))
+                }
+            })
+            .await?;
+
+        assert_eq!(1, v);
+        assert_eq!(1, runs);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_transaction_deadlock() -> Result<()> {
+        use futures03::future::join;
+
+        let factory = ConnectionFactory::from_env("MSSQL_DB")?;
+        let table = format!("##Deadlock{}", Uuid::new_v4().to_simple());
+        let create = format!(
+            "CREATE TABLE {0} (Id INT PRIMARY KEY, V INT); INSERT {0} VALUES (1, 0), (2, 0)",
+            table
+        );
+
+        // keeps the global temporary table alive during the test
+        let _setup = factory.create_connection().await?.execute(create, ()).await?;
+        let mut runs = 0;
+
+        factory
+            .run_transaction(1, |tx| {
+                runs += 1;
+
+                // on the first run, another transaction locks the rows in the opposite order
+                let other = if runs == 1 {
+                    Some(factory.create_connection())
+                } else {
+                    None
+                };
+
+                let update = {
+                    let table = table.clone();
+                    move |id: i32| format!("UPDATE {} SET V = V + 1 WHERE Id = {}", table, id)
+                };
+
+                async move {
+                    let sql = format!("SET DEADLOCK_PRIORITY LOW; {}", update(1));
+                    let tx = tx.execute(sql, ()).await?;
+
+                    let other = match other {
+                        Some(other) => other.await?.transaction().await?,
+                        None => return Ok((tx, ())),
+                    };
+
+                    let other = other.execute(update(2), ()).await?;
+
+                    // the transaction of the low priority session is chosen as the victim
+                    let (other, tx) =
+                        join(other.execute(update(1), ()), tx.execute(update(2), ())).await;
+
+                    other?.commit().await?;
+                    tx.map(|tx| (tx, ()))
+                }
+            })
+            .await?;
+
+        assert_eq!(2, runs);
+        Ok(())
+    }
+}
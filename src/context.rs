@@ -0,0 +1,308 @@
+use crate::{
+    parameter::check_length, utils::max_placeholder, ConnectionEvent, Error, LengthPolicy,
+    Parameter, QueryObserver, Result,
+};
+use std::{
+    borrow::Cow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::Span;
+
+/// What the watchdog does with a transaction idle for too long.
+///
+/// See [ConnectionFactory::transaction_idle_watchdog](struct.ConnectionFactory.html#method.transaction_idle_watchdog).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdlePolicy {
+    /// Logs a `warn!` event and continues.
+    Warn,
+    /// Rolls back the transaction and returns an
+    /// [Error::TransactionIdle](error/enum.Error.html#variant.TransactionIdle).
+    Rollback,
+}
+
+/// How the statements with parameters are sent to the server.
+///
+/// See [ConnectionFactory::execution_mode](struct.ConnectionFactory.html#method.execution_mode).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExecutionMode {
+    /// Sends the statement as is, the parameters are declared by the driver from their values.
+    /// The statements with [typed parameters](enum.Parameter.html#method.typed) or
+    /// [Ansi](struct.Ansi.html) strings still go through `sp_executesql`.
+    Driver,
+    /// Wraps the statement in `EXEC sp_executesql` with explicit declarations of the
+    /// parameters, such as `NVARCHAR(4000)` for any short string, so that the server reuses
+    /// the cached plan whatever the length of the values.
+    SpExecuteSql,
+}
+
+impl Default for ExecutionMode {
+    fn default() -> Self {
+        ExecutionMode::Driver
+    }
+}
+
+/// Settings, hooks and session state of a connection.
+///
+/// It is copied from the [ConnectionFactory](struct.ConnectionFactory.html) to the
+/// connections it creates and follows a connection into its transactions.
+#[derive(Clone, Default)]
+pub(crate) struct Context {
+    #[cfg(feature = "chaos")]
+    pub chaos: Option<Arc<crate::Chaos>>,
+    /// The current database of the session.
+    pub database: Option<String>,
+    /// Maximum duration to open the socket and log in.
+    pub connect_timeout: Option<Duration>,
+    pub execution_mode: ExecutionMode,
+    pub idle_watchdog: Option<(Duration, IdlePolicy)>,
+    /// Passes the host name of the data source to the driver instead of its ip address.
+    pub keep_hostname: bool,
+    pub max_param_len: Option<(usize, LengthPolicy)>,
+    pub observer: Option<Arc<dyn QueryObserver>>,
+    pub on_event: Option<Arc<dyn Fn(ConnectionEvent) + Send + Sync>>,
+    /// Logs the kind of the parameters instead of their values.
+    pub redact_params: bool,
+    /// Applied to the sql before it is logged.
+    pub redact_sql: Option<Arc<dyn Fn(&str) -> String + Send + Sync>>,
+    /// `@@OPTIONS` of the session, read at login.
+    pub options: i32,
+    /// `SERVERPROPERTY('ProductVersion')`, read at login.
+    pub server_version: String,
+    /// Statements executed right after connecting.
+    pub session_init: Option<Arc<str>>,
+    pub slow_query: Option<Duration>,
+    /// Maximum number of characters of the sql recorded in the spans.
+    pub sql_preview: Option<usize>,
+    /// `@@SPID` of the session, read at login.
+    pub spid: i16,
+    /// Checks that the parameters match the `@pN` placeholders of the sql.
+    pub strict_params: bool,
+    /// Shared by the clones of the context of an opened connection, reports it closed
+    /// when the last one is dropped.
+    close_guard: Option<Arc<CloseGuard>>,
+}
+
+/// Reports a [ConnectionEvent::Closed](enum.ConnectionEvent.html#variant.Closed) when dropped.
+struct CloseGuard {
+    on_event: Arc<dyn Fn(ConnectionEvent) + Send + Sync>,
+    spid: i16,
+}
+
+impl Drop for CloseGuard {
+    fn drop(&mut self) {
+        (self.on_event)(ConnectionEvent::Closed { spid: self.spid });
+    }
+}
+
+/// A statement being executed, returned by `Context::on_start`.
+pub(crate) struct Started {
+    at: Instant,
+    /// Kept only when slow queries are logged.
+    sql: Option<String>,
+}
+
+impl Context {
+    /// Injects the configured faults before sending a statement.
+    pub async fn chaos(&self) -> Result<()> {
+        #[cfg(feature = "chaos")]
+        {
+            if let Some(chaos) = &self.chaos {
+                chaos.inject().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the parameters and injects the configured faults before sending a statement.
+    ///
+    /// The statement is reported started in any case, so that a rejected statement is seen
+    /// by the observer and reported broken as any failure dropping the connection.
+    pub async fn start(&self, sql: &str, params: &mut [Parameter<'_>]) -> Result<Started> {
+        let checked = self.check_params(sql, params);
+        let started = self.on_start(sql, params);
+
+        let checked = match checked {
+            Ok(()) => self.chaos().await,
+            Err(e) => Err(e),
+        };
+
+        match checked {
+            Ok(()) => Ok(started),
+            Err(e) => {
+                let failed = Err(e);
+                self.on_end(started, &failed, |_: &Started| 0);
+                failed
+            }
+        }
+    }
+
+    /// Validates the parameters before sending a statement.
+    fn check_params(&self, sql: &str, params: &mut [Parameter]) -> Result<()> {
+        if self.strict_params {
+            let placeholders = max_placeholder(sql);
+
+            if placeholders != params.len() {
+                return Err(Error::ParameterMismatch {
+                    placeholders,
+                    params: params.len(),
+                });
+            }
+        }
+
+        match self.max_param_len {
+            Some((max, policy)) => check_length(params, max, policy),
+            None => Ok(()),
+        }
+    }
+
+    /// Called before sending a statement, inside the span of the statement.
+    fn on_start(&self, sql: &str, params: &[Parameter]) -> Started {
+        let span = Span::current();
+
+        if !span.is_disabled() {
+            let param_bytes: usize = params.iter().map(|p| p.approx_size()).sum();
+            span.record("param_bytes", &(param_bytes as u64));
+
+            let params = if self.redact_params {
+                format!("{:?}", params.iter().map(|p| p.kind()).collect::<Vec<_>>())
+            } else {
+                format!("{:?}", params)
+            };
+
+            span.record("params", &params.as_str());
+
+            if let Some(max) = self.sql_preview {
+                span.record("sql", &preview(&self.redact(sql), max).as_str());
+            }
+        }
+
+        if let Some(observer) = &self.observer {
+            observer.on_start(sql, params);
+        }
+
+        Started {
+            at: Instant::now(),
+            sql: self.slow_query.map(|_| sql.to_owned()),
+        }
+    }
+
+    /// Rewrites the sql according to the execution mode, after `on_start` so that the
+    /// statement is logged as written.
+    pub fn prepare_sql(&self, sql: Cow<'static, str>, params: &[Parameter]) -> Cow<'static, str> {
+        // the typed and ansi parameters are declared through sp_executesql whatever the mode
+        let typed = params
+            .iter()
+            .any(|p| matches!(p, Parameter::AnsiString(_) | Parameter::Typed(..)));
+
+        if typed || (self.execution_mode == ExecutionMode::SpExecuteSql && !params.is_empty()) {
+            sp_executesql(&sql, params).into()
+        } else {
+            sql
+        }
+    }
+
+    /// Called with the outcome of a statement, `rows` gives the rows read or affected.
+    pub fn on_end<T, F>(&self, started: Started, result: &Result<T>, rows: F)
+    where
+        F: FnOnce(&T) -> u64,
+    {
+        let elapsed = started.at.elapsed();
+
+        if let (Some(threshold), Some(sql)) = (self.slow_query, &started.sql) {
+            if elapsed >= threshold {
+                tracing::warn!("slow query, took {:?}: {}", elapsed, self.redact(sql));
+            }
+        }
+
+        if let Some(observer) = &self.observer {
+            match result {
+                Ok(v) => observer.on_complete(elapsed, rows(v)),
+                Err(e) => observer.on_error(e),
+            }
+        }
+
+        if result.is_err() {
+            self.notify(ConnectionEvent::Broken { spid: self.spid });
+        }
+    }
+
+    /// Reports the connection opened, and closed once the context and its clones are dropped.
+    pub fn opened(&mut self) {
+        self.notify(ConnectionEvent::Opened { spid: self.spid });
+
+        let spid = self.spid;
+        self.close_guard = self
+            .on_event
+            .clone()
+            .map(|on_event| Arc::new(CloseGuard { on_event, spid }));
+    }
+
+    /// Calls the connection event callback.
+    pub fn notify(&self, event: ConnectionEvent) {
+        if let Some(f) = &self.on_event {
+            f(event);
+        }
+    }
+
+    /// Applies the sql redaction before logging.
+    fn redact<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        match &self.redact_sql {
+            Some(f) => Cow::Owned(f(sql)),
+            None => Cow::Borrowed(sql),
+        }
+    }
+}
+
+/// Wraps the sql in `EXEC sp_executesql`, passing the parameters of the batch through.
+pub(crate) fn sp_executesql(sql: &str, params: &[Parameter]) -> String {
+    if params.is_empty() {
+        return format!("EXEC sp_executesql N'{}'", sql.replace('\'', "''"));
+    }
+
+    let decl = params
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format!("@p{} {}", i + 1, p.declared_type()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let args = (1..=params.len())
+        .map(|i| format!("@p{}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "EXEC sp_executesql N'{}', N'{}', {}",
+        sql.replace('\'', "''"),
+        decl,
+        args
+    )
+}
+
+/// Truncates the sql to `max` characters, marking the truncation with an ellipsis.
+fn preview(sql: &str, max: usize) -> String {
+    match sql.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &sql[..end]),
+        None => sql.to_owned(),
+    }
+}
+
+#[test]
+fn preview_works() {
+    assert_eq!("SELECT", preview("SELECT", 6));
+    assert_eq!("SEL…", preview("SELECT", 3));
+}
+
+#[test]
+fn sp_executesql_works() {
+    let params = [Parameter::I32(Some(1)), Parameter::String(Some("a".into()))];
+
+    assert_eq!(
+        "EXEC sp_executesql N'SELECT ''x'' WHERE @p1 = @p2', \
+         N'@p1 INT, @p2 NVARCHAR(4000)', @p1, @p2",
+        sp_executesql("SELECT 'x' WHERE @p1 = @p2", &params)
+    );
+    assert_eq!("EXEC sp_executesql N'SELECT 1'", sp_executesql("SELECT 1", &[]));
+}
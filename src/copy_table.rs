@@ -0,0 +1,316 @@
+use crate::{
+    schema, utils::quote_ident, Connection, ConnectionFactory, Error, Parameter, Result,
+    SchemaIdent,
+};
+use std::{borrow::Cow, fmt};
+use tracing::instrument;
+
+/// The maximum number of parameters of a statement, the limit of 2100 parameters of an rpc
+/// including the statement and the declarations passed to `sp_executesql`.
+const MAX_PARAMS: usize = 2100 - 2;
+
+/// The maximum number of rows of a `VALUES` clause.
+const MAX_VALUES_ROWS: usize = 1000;
+
+/// The number of rows of `columns` values inserted by a single statement.
+pub(crate) fn rows_per_insert(columns: usize) -> usize {
+    (MAX_PARAMS / columns.max(1)).min(MAX_VALUES_ROWS).max(1)
+}
+
+/// Options of [copy_table](fn.copy_table.html).
+pub struct CopyOptions {
+    batch_size: usize,
+    keep_identity: bool,
+    progress: Option<Box<dyn FnMut(u64)>>,
+}
+
+impl CopyOptions {
+    pub fn new() -> Self {
+        Self {
+            batch_size: 1000,
+            keep_identity: true,
+            progress: None,
+        }
+    }
+
+    /// The number of rows read from the source at a time, 1000 by default.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Copies the identity values instead of letting the target generate them,
+    /// `true` by default.
+    pub fn keep_identity(mut self, keep_identity: bool) -> Self {
+        self.keep_identity = keep_identity;
+        self
+    }
+
+    /// Called with the total number of rows copied after each batch.
+    pub fn progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(u64) + 'static,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CopyOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CopyOptions")
+            .field("batch_size", &self.batch_size)
+            .field("keep_identity", &self.keep_identity)
+            .finish()
+    }
+}
+
+/// A column of the source table.
+struct Column {
+    name: String,
+    ty: String,
+    is_identity: bool,
+}
+
+impl Column {
+    /// The `CONVERT` style keeping the full value when converted to and from `NVARCHAR`.
+    fn style(&self) -> i32 {
+        match self.ty.as_str() {
+            "binary" | "varbinary" => 1,
+            "money" | "smallmoney" => 2,
+            "float" | "real" => 3,
+            "date" | "datetime" | "datetime2" | "datetimeoffset" | "smalldatetime" | "time" => 126,
+            _ => 0,
+        }
+    }
+
+    fn is_binary(&self) -> bool {
+        self.ty == "binary" || self.ty == "varbinary"
+    }
+}
+
+/// Copies the rows of a table from one server to another.
+///
+/// The target table must already exist with the same column names. The rows are read
+/// by pages ordered by primary key and inserted by batches of multi-row `INSERT`s.
+/// The values are transferred as text with `CONVERT` styles that keep their full
+/// precision, and converted back to the column types by the target server.
+/// Computed and `rowversion` columns are skipped.
+///
+/// Each batch is committed on its own; on error the rows already copied stay in the
+/// target table. Returns the number of rows copied.
+///
+/// # Example
+/// ```
+/// use mssql_client::{copy_table, ConnectionFactory, CopyOptions, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let source = ConnectionFactory::from_env("MSSQL_DB")?;
+///     let target = ConnectionFactory::from_env("MSSQL_DB_TARGET")?;
+///
+///     let options = CopyOptions::new()
+///         .batch_size(5000)
+///         .progress(|rows| println!("{} rows copied", rows));
+///
+///     let orders = "dbo.Orders".parse()?;
+///     let rows = copy_table(&source, &target, &orders, options).await?;
+///     Ok(())
+/// }
+/// ```
+#[instrument(level = "debug", skip(source, target, options), err)]
+pub async fn copy_table(
+    source: &ConnectionFactory,
+    target: &ConnectionFactory,
+    table: &SchemaIdent,
+    mut options: CopyOptions,
+) -> Result<u64> {
+    let (src, columns) = read_columns(source.create_connection().await?, table).await?;
+    let (mut src, keys) = match schema::primary_key(src, table).await? {
+        (src, Some(pk)) => (src, pk.columns),
+        (_, None) => {
+            return Err(Error::String(format!(
+                "Table `{}` has no primary key to page through.",
+                table
+            )))
+        }
+    };
+
+    let name = table.to_string();
+    let join = |v: Vec<String>| v.join(", ");
+
+    let converted = join(
+        columns
+            .iter()
+            .map(|c| format!("CONVERT(NVARCHAR(MAX), {}, {})", quote_ident(&c.name), c.style()))
+            .collect(),
+    );
+
+    let select = format!(
+        "SELECT {} FROM {} ORDER BY {} OFFSET @p1 ROWS FETCH NEXT @p2 ROWS ONLY",
+        converted,
+        name,
+        join(keys.iter().map(|k| quote_ident(k)).collect())
+    );
+
+    let insert = format!(
+        "INSERT INTO {} ({}) VALUES ",
+        name,
+        join(columns.iter().map(|c| quote_ident(&c.name)).collect())
+    );
+
+    let identity_insert = options.keep_identity && columns.iter().any(|c| c.is_identity);
+    let mut dst = target.create_connection().await?;
+
+    if identity_insert {
+        dst = dst.execute(format!("SET IDENTITY_INSERT {} ON", name), ()).await?;
+    }
+
+    let rows_per_insert = rows_per_insert(columns.len());
+    let mut copied = 0u64;
+
+    loop {
+        let (s, rows): (_, Vec<Vec<Option<String>>>) = src
+            .query_map(select.clone(), (copied as i64, options.batch_size as i64), |row| {
+                (0..columns.len()).map(|i| row.get(i)).collect()
+            })
+            .await?;
+
+        src = s;
+
+        if rows.is_empty() {
+            break;
+        }
+
+        let count = rows.len();
+        let mut tx = dst.transaction().await?;
+
+        for chunk in rows.chunks(rows_per_insert) {
+            let (sql, params) = insert_sql(&insert, &columns, chunk);
+            tx = tx.execute(sql, params).await?;
+        }
+
+        dst = tx.commit().await?;
+        copied += count as u64;
+
+        if let Some(progress) = options.progress.as_mut() {
+            progress(copied);
+        }
+
+        if count < options.batch_size {
+            break;
+        }
+    }
+
+    if identity_insert {
+        dst.execute(format!("SET IDENTITY_INSERT {} OFF", name), ()).await?;
+    }
+
+    Ok(copied)
+}
+
+async fn read_columns(conn: Connection, table: &SchemaIdent) -> Result<(Connection, Vec<Column>)> {
+    let sql = r#"
+        SELECT c.name, TYPE_NAME(c.system_type_id), c.is_identity
+        FROM sys.columns c
+        WHERE c.object_id = OBJECT_ID(@p1)
+            AND c.is_computed = 0
+            AND TYPE_NAME(c.system_type_id) <> 'timestamp'
+        ORDER BY c.column_id"#;
+
+    let (conn, columns) = conn
+        .query_map(sql, table.object_name(), |row| {
+            Ok(Column {
+                name: row.get(0)?,
+                ty: row.get(1)?,
+                is_identity: row.get(2)?,
+            })
+        })
+        .await?;
+
+    if columns.is_empty() {
+        return Err(Error::String(format!("Table `{}` not found.", table)));
+    }
+
+    Ok((conn, columns))
+}
+
+/// Builds a multi-row `INSERT` converting the text values back to the column types.
+fn insert_sql(
+    insert: &str,
+    columns: &[Column],
+    rows: &[Vec<Option<String>>],
+) -> (String, Vec<Parameter<'static>>) {
+    let mut sql = insert.to_owned();
+    let mut params = Vec::with_capacity(rows.len() * columns.len());
+
+    for (r, row) in rows.iter().enumerate() {
+        if r > 0 {
+            sql.push_str(", ");
+        }
+
+        sql.push('(');
+
+        for (c, (column, value)) in columns.iter().zip(row).enumerate() {
+            if c > 0 {
+                sql.push_str(", ");
+            }
+
+            params.push(Parameter::String(value.clone().map(Cow::Owned)));
+
+            if column.is_binary() {
+                sql.push_str(&format!("CONVERT(VARBINARY(MAX), @p{}, 1)", params.len()));
+            } else {
+                sql.push_str(&format!("@p{}", params.len()));
+            }
+        }
+
+        sql.push(')');
+    }
+
+    (sql, params)
+}
+
+#[test]
+fn rows_per_insert_works() {
+    assert_eq!(1000, rows_per_insert(1));
+    assert_eq!(1000, rows_per_insert(2));
+    assert_eq!(699, rows_per_insert(3));
+    assert!(rows_per_insert(3) * 3 <= 2098);
+    assert_eq!(2, rows_per_insert(1024));
+}
+
+#[test]
+fn insert_sql_works() {
+    let columns = vec![
+        Column {
+            name: "Id".into(),
+            ty: "int".into(),
+            is_identity: true,
+        },
+        Column {
+            name: "Data".into(),
+            ty: "varbinary".into(),
+            is_identity: false,
+        },
+    ];
+
+    let rows = vec![
+        vec![Some("1".to_owned()), Some("0x01".to_owned())],
+        vec![Some("2".to_owned()), None],
+    ];
+
+    let (sql, params) = insert_sql("INSERT INTO [T] ([Id], [Data]) VALUES ", &columns, &rows);
+
+    assert_eq!(
+        "INSERT INTO [T] ([Id], [Data]) VALUES (@p1, CONVERT(VARBINARY(MAX), @p2, 1)), (@p3, CONVERT(VARBINARY(MAX), @p4, 1))",
+        sql
+    );
+    assert_eq!(4, params.len());
+}
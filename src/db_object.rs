@@ -0,0 +1,136 @@
+use crate::{
+    ident::validate,
+    utils::{quote_ident, split_name},
+    Connection, Error, Result,
+};
+use futures03::future::LocalBoxFuture;
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+/// A validated three-part object name, `database.schema.name`.
+///
+/// The database is always explicit so a cross-database query never falls back on the
+/// current database of the connection. It displays as a quoted name, ready to be
+/// formatted into a statement.
+///
+/// # Example
+/// ```
+/// use mssql_client::{DbObject, Result};
+///
+/// fn main() -> Result<()> {
+///     let orders: DbObject = "[Sales.2020].dbo.Orders".parse()?;
+///     assert_eq!("[Sales.2020].[dbo].[Orders]", orders.to_string());
+///
+///     let sql = format!("SELECT COUNT(*) FROM {}", orders);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DbObject {
+    database: String,
+    schema: String,
+    name: String,
+}
+
+impl DbObject {
+    /// Creates an object name from unquoted parts.
+    ///
+    /// An error is returned if a part is empty, longer than the 128 characters of a `sysname`
+    /// or contains control characters.
+    pub fn new(database: &str, schema: &str, name: &str) -> Result<Self> {
+        validate("database", database)?;
+        validate("schema", schema)?;
+        validate("name", name)?;
+
+        Ok(Self {
+            database: database.to_owned(),
+            schema: schema.to_owned(),
+            name: name.to_owned(),
+        })
+    }
+
+    pub fn database(&self) -> &str {
+        &self.database
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn schema(&self) -> &str {
+        &self.schema
+    }
+}
+
+impl Display for DbObject {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}",
+            quote_ident(&self.database),
+            quote_ident(&self.schema),
+            quote_ident(&self.name)
+        )
+    }
+}
+
+impl FromStr for DbObject {
+    type Err = Error;
+
+    /// Parses a three-part name, each part optionally quoted with brackets.
+    fn from_str(s: &str) -> Result<Self> {
+        match split_name(s).as_slice() {
+            [database, schema, name] => Self::new(database, schema, name),
+            _ => Err(Error::String(format!(
+                "`{}` is not a three-part name (database.schema.name).",
+                s
+            ))),
+        }
+    }
+}
+
+impl Connection {
+    /// Returns the id of an object, or `None` if the database or the object does not exist.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, DbObject, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let objects = DbObject::new("tempdb", "sys", "objects")?;
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let (connection, id) = connection.object_id(&objects).await?;
+    ///
+    ///     assert!(id.is_some());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn object_id(
+        self,
+        object: &DbObject,
+    ) -> LocalBoxFuture<'static, Result<(Self, Option<i32>)>> {
+        let name = object.to_string();
+
+        Box::pin(async move {
+            let (conn, rows): (_, Vec<Option<i32>>) =
+                self.query("SELECT OBJECT_ID(@p1)", name).await?;
+            Ok((conn, rows.into_iter().next().flatten()))
+        })
+    }
+}
+
+#[test]
+fn db_object_works() {
+    let o: DbObject = "master.dbo.[My]]Table]".parse().unwrap();
+    assert_eq!("master", o.database());
+    assert_eq!("dbo", o.schema());
+    assert_eq!("My]Table", o.name());
+    assert_eq!("[master].[dbo].[My]]Table]", o.to_string());
+
+    assert!("dbo.Orders".parse::<DbObject>().is_err());
+    assert!("master..Orders".parse::<DbObject>().is_err());
+    assert!(DbObject::new("master", "dbo", &"x".repeat(129)).is_err());
+}
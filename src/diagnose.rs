@@ -0,0 +1,183 @@
+use crate::{
+    utils::{resolve, split_datasource, unblock},
+    ConnectionFactory, Error,
+};
+use conn_str::MsSqlConnStr;
+use futures03::future::{select, Either};
+use futures_timer::Delay;
+use std::{
+    fmt, io,
+    net::{IpAddr, SocketAddr, TcpStream},
+    str::FromStr,
+    time::Duration,
+};
+
+/// How long the tcp stage waits for the server.
+const TCP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A stage of a connection attempt, checked by
+/// [ConnectionFactory::diagnose](struct.ConnectionFactory.html#method.diagnose).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    /// Parsing the connection string.
+    ConnectionString,
+    /// Resolving the server name into an ip address.
+    Dns,
+    /// Opening a tcp connection to the server port.
+    Tcp,
+    /// Negotiating TLS and logging in.
+    Login,
+}
+
+impl Stage {
+    fn hint(self) -> &'static str {
+        match self {
+            Stage::ConnectionString => {
+                "Check the syntax of the connection string and that the server is specified."
+            }
+            Stage::Dns => {
+                "Check the server name, the DNS configuration of this machine, or use an ip address."
+            }
+            Stage::Tcp => {
+                "Check that SQL Server accepts TCP/IP connections on this port and that no firewall blocks it."
+            }
+            Stage::Login => {
+                "Check the credentials, the encrypt and trustservercertificate settings, and that the login can access the database."
+            }
+        }
+    }
+}
+
+/// The report of [ConnectionFactory::diagnose](struct.ConnectionFactory.html#method.diagnose).
+#[derive(Debug)]
+pub struct Diagnosis {
+    /// The stages that succeeded, in order.
+    pub passed: Vec<Stage>,
+    /// The stage that failed, with its error.
+    pub failed: Option<(Stage, Error)>,
+}
+
+impl Diagnosis {
+    /// Returns true if all the stages succeeded.
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_none()
+    }
+
+    /// Returns a remediation hint for the failed stage.
+    pub fn hint(&self) -> Option<&'static str> {
+        self.failed.as_ref().map(|(stage, _)| stage.hint())
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for stage in &self.passed {
+            writeln!(f, "{:?}: ok", stage)?;
+        }
+
+        if let Some((stage, e)) = &self.failed {
+            writeln!(f, "{:?}: failed, {}", stage, e)?;
+            write!(f, "{}", stage.hint())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ConnectionFactory {
+    /// Checks each stage of a connection separately and reports the first one failing
+    /// with a remediation hint.
+    ///
+    /// The tcp stage is skipped for a named instance without a port.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let diagnosis = ConnectionFactory::from_env("MSSQL_DB")?.diagnose().await;
+    ///
+    ///     if !diagnosis.is_ok() {
+    ///         eprintln!("{}", diagnosis);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn diagnose(&self) -> Diagnosis {
+        let mut passed = Vec::new();
+
+        match self.diagnose_imp(&mut passed).await {
+            Ok(()) => Diagnosis {
+                passed,
+                failed: None,
+            },
+            Err((stage, e)) => Diagnosis {
+                passed,
+                failed: Some((stage, e)),
+            },
+        }
+    }
+
+    async fn diagnose_imp(&self, passed: &mut Vec<Stage>) -> Result<(), (Stage, Error)> {
+        let stage = Stage::ConnectionString;
+
+        let data_source = MsSqlConnStr::from_str(&self.conn_str)
+            .map_err(|e| (stage, e.into()))?
+            .data_source()
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.to_owned())
+            .ok_or((stage, Error::DataSourceNotSpecified))?;
+
+        let (machine, instance, port) = split_datasource(&data_source);
+        let port = match port {
+            Some(p) => Some(
+                p.trim()
+                    .parse::<u16>()
+                    .map_err(|_| (stage, Error::String(format!("Invalid port `{}`.", p))))?,
+            ),
+            None if instance.is_none() => Some(1433),
+            None => None,
+        };
+
+        passed.push(stage);
+
+        let stage = Stage::Dns;
+        let host = machine.clone();
+        let ip = unblock(move || resolve(&host)).await.map_err(|e| (stage, e))?;
+        let ip = IpAddr::from_str(&ip).map_err(|_| (stage, Error::HostNotFound(machine)))?;
+        passed.push(stage);
+
+        if let Some(port) = port {
+            let stage = Stage::Tcp;
+            let addr = SocketAddr::new(ip, port);
+
+            // the thread also gives up after the timeout
+            let connect = unblock(move || {
+                TcpStream::connect_timeout(&addr, TCP_TIMEOUT)?;
+                Ok(())
+            });
+
+            match select(Box::pin(connect), Delay::new(TCP_TIMEOUT)).await {
+                Either::Left((r, _)) => r.map_err(|e| (stage, e))?,
+                Either::Right(_) => {
+                    let e = io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("tcp connection timed out after {:?}", TCP_TIMEOUT),
+                    );
+
+                    return Err((stage, Error::Io(e)));
+                }
+            }
+
+            passed.push(stage);
+        }
+
+        let stage = Stage::Login;
+        self.create_connection().await.map_err(|e| (stage, e))?;
+        passed.push(stage);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Command, Parameter, Params, Result, Row};
+use futures03::future::LocalBoxFuture;
+use std::{borrow::Cow, fmt::Debug};
+
+/// An object-safe version of [Command](trait.Command.html), so that code can hold a
+/// `Box<dyn DynCommand>` chosen at runtime, such as a [Connection](struct.Connection.html)
+/// or a [Transaction](struct.Transaction.html).
+///
+/// It is implemented for every `Command`, and `Box<dyn DynCommand>` implements `Command`,
+/// so all its methods are available on the box.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Command, Connection, DynCommand, Result};
+///
+/// async fn count(c: Box<dyn DynCommand>) -> Result<(Box<dyn DynCommand>, i32)> {
+///     c.query_scalar("SELECT COUNT(*) FROM sys.objects", ()).await
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let connection = Connection::from_env("MSSQL_DB").await?;
+///     let (_, n) = count(Box::new(connection)).await?;
+///
+///     assert!(n > 0);
+///     Ok(())
+/// }
+/// ```
+pub trait DynCommand {
+    /// Executes an sql command that does not returns rows.
+    fn execute_dyn<'a>(
+        self: Box<Self>,
+        sql: String,
+        params: Vec<Parameter<'a>>,
+    ) -> LocalBoxFuture<'a, Result<Box<dyn DynCommand>>>;
+
+    /// Queries the database and calls `func` with each row.
+    fn query_dyn<'a>(
+        self: Box<Self>,
+        sql: String,
+        params: Vec<Parameter<'a>>,
+        func: &'a mut dyn FnMut(&Row) -> Result<()>,
+    ) -> LocalBoxFuture<'a, Result<Box<dyn DynCommand>>>;
+}
+
+impl<C> DynCommand for C
+where
+    C: Command + 'static,
+{
+    fn execute_dyn<'a>(
+        self: Box<Self>,
+        sql: String,
+        params: Vec<Parameter<'a>>,
+    ) -> LocalBoxFuture<'a, Result<Box<dyn DynCommand>>> {
+        let execute = Command::execute(*self, sql, params);
+
+        Box::pin(async move { Ok(Box::new(execute.await?) as Box<dyn DynCommand>) })
+    }
+
+    fn query_dyn<'a>(
+        self: Box<Self>,
+        sql: String,
+        params: Vec<Parameter<'a>>,
+        func: &'a mut dyn FnMut(&Row) -> Result<()>,
+    ) -> LocalBoxFuture<'a, Result<Box<dyn DynCommand>>> {
+        let query = Command::query_fold(*self, sql, params, (), move |(), row| func(row));
+
+        Box::pin(async move {
+            let (c, ()) = query.await?;
+            Ok(Box::new(c) as Box<dyn DynCommand>)
+        })
+    }
+}
+
+impl Command for Box<dyn DynCommand> {
+    fn execute<'a, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<Self>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+    {
+        let mut p = Vec::new();
+        params.params(&mut p);
+
+        <dyn DynCommand>::execute_dyn(self, sql.into().into_owned(), p)
+    }
+
+    fn query_fold<'a, T, S, P, F>(
+        self,
+        sql: S,
+        params: P,
+        init: T,
+        mut func: F,
+    ) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        F: FnMut(T, &Row) -> Result<T> + 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: 'a,
+        Self: Sized,
+    {
+        let sql = sql.into().into_owned();
+        let mut p = Vec::new();
+        params.params(&mut p);
+
+        Box::pin(async move {
+            // the accumulator is taken out and put back around each call of `func`
+            let mut acc = Some(init);
+
+            let mut next = |row: &Row| -> Result<()> {
+                let value = acc.take().expect("acc");
+                acc = Some(func(value, row)?);
+                Ok(())
+            };
+
+            let c = <dyn DynCommand>::query_dyn(self, sql, p, &mut next).await?;
+            Ok((c, acc.expect("acc")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+
+    #[tokio::test]
+    async fn dyn_command() -> Result<()> {
+        let connection = Connection::from_env("MSSQL_DB").await?;
+        let transaction = Connection::from_env("MSSQL_DB").await?.transaction().await?;
+        let commands: Vec<Box<dyn DynCommand>> =
+            vec![Box::new(connection), Box::new(transaction)];
+
+        for c in commands {
+            let c = c.execute("CREATE TABLE #T (V INT)", ()).await?;
+            let c = c.execute("INSERT #T VALUES (@p1), (@p2)", (1, 2)).await?;
+            let (_, rows): (_, Vec<i32>) = c.query("SELECT V FROM #T ORDER BY V", ()).await?;
+            assert_eq!(vec![1, 2], rows);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{
+    export::column_names, sql_value::column_type, Connection, FromRow, Params, Result, Row,
+    SqlValue,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use decimal::Decimal;
+use futures03::future::LocalBoxFuture;
+use std::{borrow::Cow, ops::Deref};
+use uuid::Uuid;
+
+/// Reads a column of a known type.
+type Decoder = fn(&Row, usize) -> Result<SqlData>;
+
+/// A column value of any of the supported types, read by [DynamicRow](struct.DynamicRow.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqlData {
+    Null,
+    Binary(Vec<u8>),
+    Bool(bool),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    Decimal(Decimal),
+    /// A `float` or `money` column.
+    F64(f64),
+    /// A `real` or `smallmoney` column.
+    F32(f32),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    /// A `tinyint` column, which is unsigned.
+    U8(u8),
+    String(String),
+    Uuid(Uuid),
+}
+
+impl SqlData {
+    pub fn is_null(&self) -> bool {
+        *self == SqlData::Null
+    }
+
+    /// Reads a column by trying the supported types, narrowest integers first, for the
+    /// nulls and the columns of an unknown type.
+    fn read(row: &Row, idx: usize) -> Result<Self> {
+        fn get<'a, T, F>(row: &'a Row, idx: usize, f: F) -> Option<SqlData>
+        where
+            Option<T>: SqlValue<'a>,
+            F: FnOnce(T) -> SqlData,
+        {
+            <Option<T>>::from_row(row, idx).ok().map(|v| v.map_or(SqlData::Null, f))
+        }
+
+        let found = get(row, idx, SqlData::Bool)
+            .or_else(|| get(row, idx, SqlData::U8))
+            .or_else(|| get(row, idx, SqlData::I16))
+            .or_else(|| get(row, idx, SqlData::I32))
+            .or_else(|| get(row, idx, SqlData::I64))
+            .or_else(|| get(row, idx, SqlData::F32))
+            .or_else(|| get(row, idx, SqlData::F64))
+            .or_else(|| get(row, idx, SqlData::Decimal))
+            .or_else(|| get(row, idx, |v: &str| SqlData::String(v.to_owned())))
+            .or_else(|| get(row, idx, |v: &[u8]| SqlData::Binary(v.to_vec())))
+            .or_else(|| get(row, idx, SqlData::DateTime))
+            .or_else(|| get(row, idx, SqlData::Date));
+
+        match found {
+            Some(v) => Ok(v),
+            None => {
+                // the last type reports the error, the column is missing or unsupported
+                let v = <Option<Uuid>>::from_row(row, idx)?;
+                Ok(v.map_or(SqlData::Null, SqlData::Uuid))
+            }
+        }
+    }
+}
+
+/// Reads the value of a column with the decoder of its type.
+fn get<'a, T, F>(row: &'a Row, idx: usize, f: F) -> Result<SqlData>
+where
+    Option<T>: SqlValue<'a>,
+    F: FnOnce(T) -> SqlData,
+{
+    Ok(<Option<T>>::from_row(row, idx)?.map_or(SqlData::Null, f))
+}
+
+/// The decoder of a type named by `column_type`.
+fn decoder(sql_type: &str) -> Option<Decoder> {
+    let d: Decoder = match sql_type {
+        "bigint" => |row, idx| get(row, idx, SqlData::I64),
+        "int" => |row, idx| get(row, idx, SqlData::I32),
+        "smallint" => |row, idx| get(row, idx, SqlData::I16),
+        "tinyint" => |row, idx| get(row, idx, SqlData::U8),
+        "bit" => |row, idx| get(row, idx, SqlData::Bool),
+        "float" => |row, idx| get(row, idx, SqlData::F64),
+        "real" => |row, idx| get(row, idx, SqlData::F32),
+        "decimal" => |row, idx| get(row, idx, SqlData::Decimal),
+        "nvarchar" => |row, idx| get(row, idx, |v: &str| SqlData::String(v.to_owned())),
+        "varbinary" => |row, idx| get(row, idx, |v: &[u8]| SqlData::Binary(v.to_vec())),
+        "datetime2" => |row, idx| get(row, idx, SqlData::DateTime),
+        "date" => |row, idx| get(row, idx, SqlData::Date),
+        "uniqueidentifier" => |row, idx| get(row, idx, SqlData::Uuid),
+        _ => return None,
+    };
+
+    Some(d)
+}
+
+/// Reads the rows of a result set, choosing the decoder of each column from its first
+/// non null value.
+#[derive(Default)]
+pub(crate) struct Decoders(Vec<Option<Decoder>>);
+
+impl Decoders {
+    pub(crate) fn read(&mut self, row: &Row) -> Result<DynamicRow> {
+        self.0.resize(row.len(), None);
+
+        self.0
+            .iter_mut()
+            .enumerate()
+            .map(|(idx, d)| {
+                if d.is_none() {
+                    *d = column_type(row, idx).and_then(decoder);
+                }
+
+                match *d {
+                    Some(d) => d(row, idx),
+                    None => SqlData::read(row, idx),
+                }
+            })
+            .collect::<Result<_>>()
+            .map(DynamicRow)
+    }
+}
+
+/// A row read without knowing its columns at compile time, holding one
+/// [SqlData](enum.SqlData.html) per column.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Connection, DynamicRow, Result, SqlData};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let (_connection, rows): (_, Vec<DynamicRow>) = Connection::from_env("MSSQL_DB")
+///         .await?
+///         .query("SELECT 1, N'a', NULL", ())
+///         .await?;
+///
+///     assert_eq!(SqlData::I32(1), rows[0][0]);
+///     assert_eq!(SqlData::String("a".into()), rows[0][1]);
+///     assert!(rows[0][2].is_null());
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DynamicRow(pub Vec<SqlData>);
+
+impl Deref for DynamicRow {
+    type Target = [SqlData];
+
+    fn deref(&self) -> &[SqlData] {
+        &self.0
+    }
+}
+
+impl From<DynamicRow> for Vec<SqlData> {
+    fn from(v: DynamicRow) -> Self {
+        v.0
+    }
+}
+
+/// Each value is read with the decoder of its type. Use
+/// [query_dynamic](struct.Connection.html#method.query_dynamic) to choose the decoders once
+/// per column and read the column names.
+impl FromRow for DynamicRow {
+    fn from_row(row: &Row) -> Result<Self> {
+        Decoders::default().read(row)
+    }
+}
+
+/// The rows read by [query_dynamic](struct.Connection.html#method.query_dynamic), with the
+/// names of their columns.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DynamicRows {
+    pub columns: Vec<String>,
+    pub rows: Vec<DynamicRow>,
+}
+
+impl DynamicRows {
+    /// The index of the column named `name`.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c == name)
+    }
+
+    /// The value of the column named `name` in the row at `index`.
+    pub fn get(&self, index: usize, name: &str) -> Option<&SqlData> {
+        self.rows.get(index)?.get(self.column(name)?)
+    }
+}
+
+impl Connection {
+    /// Runs a query and reads its rows as [DynamicRow](struct.DynamicRow.html), along with
+    /// the names of the columns.
+    ///
+    /// The decoder of a column is chosen from its first non null value and reused for the
+    /// following rows. See [query_to_csv](#method.query_to_csv) for how the column names are
+    /// read.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result, SqlData};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let (_connection, result) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .query_dynamic("SELECT 1 AS Id, N'a' AS Name", ())
+    ///         .await?;
+    ///
+    ///     assert_eq!(vec!["Id", "Name"], result.columns);
+    ///     assert_eq!(Some(&SqlData::String("a".into())), result.get(0, "Name"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_dynamic<'a, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, DynamicRows)>>
+    where
+        P: Params<'a> + 'a,
+        S: Into<Cow<'static, str>>,
+    {
+        let sql = sql.into();
+        let mut p = Vec::new();
+        params.params(&mut p);
+
+        Box::pin(async move {
+            let (conn, columns) = column_names(self, &sql, &p).await?;
+            let mut decoders = Decoders::default();
+            let (conn, rows) = conn.query_map(sql, p, move |row| decoders.read(row)).await?;
+
+            Ok((conn, DynamicRows { columns, rows }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+
+    #[tokio::test]
+    async fn dynamic_row() -> Result<()> {
+        let sql = r#"
+            SELECT
+                CAST(1 AS BIT), CAST(200 AS TINYINT), CAST(3 AS SMALLINT), 4, CAST(5 AS BIGINT),
+                CAST(1.5 AS FLOAT), CAST(2.5 AS DECIMAL(5, 2)), N'a', 0x0102,
+                CAST('2020-01-02' AS DATE), CAST(NULL AS INT)"#;
+
+        let (_, rows): (_, Vec<DynamicRow>) =
+            Connection::from_env("MSSQL_DB").await?.query(sql, ()).await?;
+
+        let row = &rows[0];
+        assert_eq!(11, row.len());
+        assert_eq!(SqlData::Bool(true), row[0]);
+        assert_eq!(SqlData::U8(200), row[1]);
+        assert_eq!(SqlData::I16(3), row[2]);
+        assert_eq!(SqlData::I32(4), row[3]);
+        assert_eq!(SqlData::I64(5), row[4]);
+        assert_eq!(SqlData::F64(1.5), row[5]);
+        assert!(matches!(row[6], SqlData::Decimal(_)));
+        assert_eq!(SqlData::String("a".into()), row[7]);
+        assert_eq!(SqlData::Binary(vec![1, 2]), row[8]);
+        assert_eq!(SqlData::Date(NaiveDate::from_ymd(2020, 1, 2)), row[9]);
+        assert_eq!(SqlData::Null, row[10]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_dynamic() -> Result<()> {
+        let sql = "SELECT v AS Value, N'x' AS Name FROM (VALUES (NULL), (2), (3)) t(v)";
+        let (_, result) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query_dynamic(sql, ())
+            .await?;
+
+        assert_eq!(vec!["Value", "Name"], result.columns);
+        assert_eq!(Some(1), result.column("Name"));
+        assert_eq!(Some(&SqlData::Null), result.get(0, "Value"));
+        assert_eq!(Some(&SqlData::I32(3)), result.get(2, "Value"));
+        assert_eq!(Some(&SqlData::String("x".into())), result.get(1, "Name"));
+        assert_eq!(None, result.get(0, "Missing"));
+        Ok(())
+    }
+}
@@ -3,14 +3,49 @@ use std::fmt;
 #[derive(Debug)]
 pub enum Error {
     Box(Box<dyn std::error::Error>),
+    /// A column could not be converted, the type it was decoded as does not match the rust
+    /// type.
+    ColumnTypeMismatch {
+        index: usize,
+        column_name: Option<&'static str>,
+        /// The sql type of the value decoded by the driver, which may differ from the declared
+        /// type of the column, such as `datetime2` for a `datetime` or `float` for a `money`.
+        decoded_as: &'static str,
+        rust_type: &'static str,
+    },
     ConnStr(conn_str::Error),
     DataSourceNotSpecified,
     FieldName(Box<dyn std::error::Error>, &'static str),
     FieldNotFound(usize),
     HostNotFound(String),
     Io(std::io::Error),
+    NoRows,
+    ParameterMismatch {
+        placeholders: usize,
+        params: usize,
+    },
+    ParameterTooLong {
+        index: usize,
+        len: usize,
+        max: usize,
+    },
+    /// A row could not be converted or the function reading it failed, `row` is its index in
+    /// the result set.
+    RowConversion {
+        row: u64,
+        source: Box<Error>,
+    },
+    SessionBroken,
     Tiberius(tiberius::Error),
     TiberiusField(tiberius::Error, usize),
+    TooManyRows(u64),
+    TransactionIdle(std::time::Duration),
+    /// A null was read into a type that is not an `Option`.
+    UnexpectedNull {
+        index: usize,
+        column_name: Option<&'static str>,
+    },
+    UnsupportedProtocol(String),
     Str(&'static str),
     String(String),
     Var(std::env::VarError),
@@ -20,6 +55,19 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
             Self::Box(e) => e.fmt(f),
+            Self::ColumnTypeMismatch {
+                index,
+                column_name,
+                decoded_as,
+                rust_type,
+            } => {
+                match column_name {
+                    Some(name) => write!(f, "Column `{}` (index {})", name, index)?,
+                    None => write!(f, "Column {}", index)?,
+                }
+
+                write!(f, " decoded as `{}` cannot be read as `{}`.", decoded_as, rust_type)
+            }
             Self::ConnStr(e) => e.fmt(f),
             Self::DataSourceNotSpecified => {
                 f.write_str("Data source / server not specified in connection string.")
@@ -28,16 +76,129 @@ impl fmt::Display for Error {
             Self::FieldNotFound(i) => write!(f, "FieldIndex: `{}` not found.", i),
             Self::HostNotFound(s) => write!(f, "Host `{}` not found", s),
             Self::Io(e) => e.fmt(f),
+            Self::NoRows => f.write_str("The query returned no rows."),
+            Self::ParameterMismatch {
+                placeholders,
+                params,
+            } => write!(
+                f,
+                "The sql references {} parameters (`@P{}`) but {} are bound.",
+                placeholders, placeholders, params
+            ),
+            Self::ParameterTooLong { index, len, max } => write!(
+                f,
+                "Parameter `@P{}` has {} characters, the maximum is {}.",
+                index + 1,
+                len,
+                max
+            ),
+            Self::RowConversion { row, source } => write!(f, "Row {}: {}", row, source),
+            Self::SessionBroken => {
+                f.write_str("The session lost its connection after a previous error.")
+            }
             Self::Str(e) => e.fmt(f),
             Self::String(e) => e.fmt(f),
             Self::Tiberius(e) => write!(f, "{:?}", e),
             Self::TiberiusField(e, i) => write!(f, "{:?}, Field index `{}`", e, i),
+            Self::TooManyRows(n) => write!(f, "The query returned {} rows, expected one.", n),
+            Self::TransactionIdle(d) => {
+                write!(f, "Transaction rolled back after being idle for {:?}.", d)
+            }
+            Self::UnexpectedNull {
+                index,
+                column_name: Some(name),
+            } => write!(
+                f,
+                "Column `{}` (index {}) is null, read it into an Option.",
+                name, index
+            ),
+            Self::UnexpectedNull {
+                index,
+                column_name: None,
+            } => write!(f, "Column {} is null, read it into an Option.", index),
+            Self::UnsupportedProtocol(p) => write!(
+                f,
+                "The `{}` protocol is not supported, the data source must use tcp.",
+                p
+            ),
             Self::Var(e) => e.fmt(f),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl Error {
+    /// The number of the error raised by the server, such as `1205` for a deadlock or `2627`
+    /// for a violation of a unique constraint, `None` for the other errors.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let e = connection.execute("SELECT * FROM #Missing", ()).await.unwrap_err();
+    ///
+    ///     assert_eq!(Some(208), e.code());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            Self::Tiberius(tiberius::Error::Server(e)) => Some(e.code),
+            Self::TiberiusField(tiberius::Error::Server(e), _) => Some(e.code),
+            _ => None,
+        }
+    }
+
+    /// The state of the error raised by the server, which tells apart the causes of a same
+    /// error number, `None` for the other errors.
+    ///
+    /// SQL Server does not send an ODBC `SQLSTATE`, the state is the number shown in
+    /// `Msg 208, Level 16, State 1`.
+    pub fn state(&self) -> Option<u8> {
+        match self {
+            Self::Tiberius(tiberius::Error::Server(e)) => Some(e.state),
+            Self::TiberiusField(tiberius::Error::Server(e), _) => Some(e.state),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for a deadlock or a snapshot isolation update conflict, after which
+    /// the transaction can be run again.
+    pub(crate) fn is_transient_conflict(&self) -> bool {
+        matches!(self.code(), Some(1205) | Some(3960))
+    }
+
+    /// Adds the index of the row to an error returned while reading it.
+    pub(crate) fn at_row(self, row: u64) -> Self {
+        match self {
+            e @ Self::RowConversion { .. } => e,
+            e => Self::RowConversion {
+                row,
+                source: Box::new(e),
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    /// The wrapping variants, whose message is the message of the inner error, return its
+    /// source; the variants adding a context to an inner error return the inner error.
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Box(e) => e.source(),
+            Self::ConnStr(e) => e.source(),
+            Self::FieldName(e, _) => Some(e.as_ref()),
+            Self::Io(e) => e.source(),
+            Self::RowConversion { source, .. } => Some(source.as_ref()),
+            Self::Tiberius(e) => e.source(),
+            Self::TiberiusField(e, _) => Some(e),
+            Self::Var(e) => e.source(),
+            _ => None,
+        }
+    }
+}
 
 impl From<Box<dyn std::error::Error + 'static>> for Error {
     fn from(e: Box<dyn std::error::Error + 'static>) -> Self {
@@ -89,3 +250,30 @@ impl From<std::env::VarError> for Error {
         Self::Var(e)
     }
 }
+
+#[test]
+fn source_works() {
+    use std::error::Error as _;
+
+    let e = Error::RowConversion {
+        row: 3,
+        source: Box::new(Error::Str("bad value")),
+    };
+
+    assert_eq!("Row 3: bad value", e.to_string());
+    assert_eq!("bad value", e.source().unwrap().to_string());
+    assert!(Error::Str("bad value").source().is_none());
+    assert_eq!("Row 3: bad value", e.at_row(5).to_string());
+
+    let e = Error::ColumnTypeMismatch {
+        index: 1,
+        column_name: Some("created"),
+        decoded_as: "datetime2",
+        rust_type: "i32",
+    };
+
+    assert_eq!(
+        "Column `created` (index 1) decoded as `datetime2` cannot be read as `i32`.",
+        e.to_string()
+    );
+}
@@ -1,5 +1,8 @@
 /// Execute a sql statement using named parameters.
 ///
+/// A parameter used more than once in the sql is bound once, all its occurrences being
+/// replaced by the same `@PN` placeholder.
+///
 /// # Example
 ///
 /// ```
@@ -22,27 +25,133 @@
 macro_rules! execute_sql {
     ($command:expr, $sql:expr, $($fname:ident = $fvalue:expr),* $(,)*) => {
         {
-            let sql = {
-                let sql: &'static str = $sql;
-                let mut sql = sql.to_owned();
-                let mut i = 1;
-
-                $(
-                    $crate::replace_params(&mut sql, stringify!($fname), &format!("P{}", i));
-                    #[allow(unused_assignments)]
-                    {
-                        i += 1;
-                    }
-                )*
-
-                sql
-            };
-
+            let sql = $crate::__named_sql!($sql, $($fname),*);
             $command.execute(sql, ($($fvalue,)*))
         }
     };
 }
 
+/// Query the database using named parameters and reads all rows.
+///
+/// The parameters are replaced as in [execute_sql](macro.execute_sql.html).
+///
+/// # Example
+///
+/// ```
+/// use mssql_client::{query_sql, Connection, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let conn = Connection::from_env("MSSQL_DB").await?;
+///     let (_conn, rows): (_, Vec<i32>) = query_sql!(
+///         conn,
+///         "SELECT @id WHERE @id > @min",
+///         id = 55,
+///         min = 10
+///     ).await?;
+///
+///     assert_eq!(vec![55], rows);
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! query_sql {
+    ($command:expr, $sql:expr, $($fname:ident = $fvalue:expr),* $(,)*) => {
+        {
+            let sql = $crate::__named_sql!($sql, $($fname),*);
+            $command.query(sql, ($($fvalue,)*))
+        }
+    };
+}
+
+/// Query the database using named parameters and reads all rows into a struct declared
+/// by the macro, whose fields are read from the columns in order.
+///
+/// The parameters are replaced as in [execute_sql](macro.execute_sql.html). The struct is
+/// declared inside the expression, so it can only be named by the code of the macro; its
+/// fields are accessed through the returned rows.
+///
+/// # Example
+///
+/// ```
+/// use mssql_client::{query_as, Connection, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let conn = Connection::from_env("MSSQL_DB").await?;
+///     let (_conn, rows) = query_as!(
+///         conn,
+///         Account { id: i32, name: String },
+///         "SELECT @id, N'Foo' WHERE @id > @min",
+///         id = 3,
+///         min = 1
+///     ).await?;
+///
+///     assert_eq!(3, rows[0].id);
+///     assert_eq!("Foo", rows[0].name);
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! query_as {
+    (
+        $command:expr,
+        $name:ident { $($field:ident : $ty:ty),* $(,)* },
+        $sql:expr
+        $(, $fname:ident = $fvalue:expr)* $(,)*
+    ) => {
+        {
+            #[derive(Clone, Debug)]
+            struct $name {
+                $($field: $ty,)*
+            }
+
+            impl $crate::FromRow for $name {
+                #[allow(unused_assignments, unused_mut, unused_variables)]
+                fn from_row(row: &$crate::Row) -> $crate::Result<Self> {
+                    let mut idx = 0;
+
+                    Ok($name {
+                        $(
+                            $field: {
+                                idx += 1;
+                                row.get_named_err(idx - 1, stringify!($field))?
+                            },
+                        )*
+                    })
+                }
+            }
+
+            let sql = $crate::__named_sql!($sql, $($fname),*);
+            $command.query::<$name, _, _>(sql, ($($fvalue,)*))
+        }
+    };
+}
+
+/// Replaces the named parameters of the sql by `@P1`, `@P2`, ... in order.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __named_sql {
+    ($sql:expr, $($fname:ident),*) => {
+        {
+            let sql: &'static str = $sql;
+            let mut sql = sql.to_owned();
+            #[allow(unused_mut, unused_variables)]
+            let mut i = 1;
+
+            $(
+                $crate::replace_params(&mut sql, stringify!($fname), &format!("P{}", i));
+                #[allow(unused_assignments)]
+                {
+                    i += 1;
+                }
+            )*
+
+            sql
+        }
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Result;
@@ -81,4 +190,64 @@ mod tests {
         assert_eq!("Foo", &rows[0].1);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn macros_on_connection_and_transaction() -> Result<()> {
+        use crate::{Command, Connection};
+
+        async fn run<C: Command + 'static>(c: C) -> Result<C> {
+            let c = c.execute("CREATE TABLE #Temp (Id int)", ()).await?;
+            let c = execute_sql!(c, "INSERT #Temp VALUES (@a), (@b)", a = 1, b = 2).await?;
+            let (c, rows): (_, Vec<i32>) =
+                query_sql!(c, "SELECT Id FROM #Temp WHERE Id >= @min", min = 2).await?;
+
+            assert_eq!(vec![2], rows);
+            Ok(c)
+        }
+
+        run(Connection::from_env("MSSQL_DB").await?).await?;
+
+        let transaction = Connection::from_env("MSSQL_DB").await?.transaction().await?;
+        run(transaction).await?.commit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_as_works() -> Result<()> {
+        use crate::Connection;
+
+        let conn = Connection::from_env("MSSQL_DB").await?;
+        let (conn, rows) = query_as!(
+            conn,
+            Item { id: i32, name: Option<String> },
+            "SELECT @id, NULL UNION ALL SELECT @id + 1, @name",
+            id = 1,
+            name = "b",
+        )
+        .await?;
+
+        assert_eq!(2, rows.len());
+        assert_eq!((1, None), (rows[0].id, rows[0].name.clone()));
+        assert_eq!((2, Some("b".to_owned())), (rows[1].id, rows[1].name.clone()));
+
+        let (_, rows) = query_as!(conn, Empty {}, "SELECT 1").await?;
+        assert_eq!(1, rows.len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn execute_reuses_param() -> Result<()> {
+        use crate::Connection;
+
+        let conn = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #Temp (Id int, ParentId int)", ())
+            .await?;
+
+        let conn = execute_sql!(conn, "INSERT #Temp VALUES (@id, @Id + 1)", id = 7).await?;
+        let (_, rows): (_, Vec<(i32, i32)>) = conn.query("SELECT * FROM #Temp", ()).await?;
+
+        assert_eq!(vec![(7, 8)], rows);
+        Ok(())
+    }
 }
@@ -0,0 +1,433 @@
+use crate::{dynamic_row::Decoders, Connection, Error, Parameter, Params, Result, Row, SqlData};
+use futures03::{
+    future::LocalBoxFuture,
+    io::{AsyncWrite, AsyncWriteExt},
+};
+use std::{borrow::Cow, collections::HashMap, fmt::Write as _};
+
+impl Connection {
+    /// Runs a query and writes its rows to `writer` as CSV (RFC 4180), with a header
+    /// line holding the column names. Returns the number of rows written.
+    ///
+    /// The rows are written as they are received, to a futures `AsyncWrite` like the
+    /// reader of [write_blob](#method.write_blob); a `std::io::Write` can be wrapped in
+    /// `futures::io::AllowStdIo`. Nulls are written as empty fields, binary values in
+    /// hexadecimal (`0x...`) and dates in ISO 8601 format.
+    ///
+    /// # Column names
+    /// The column names are read beforehand with `sys.dm_exec_describe_first_result_set`,
+    /// which costs one more round trip and cannot describe the statements using temporary
+    /// tables created in the same batch; the query then fails before running.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut csv = Vec::new();
+    ///     let (_connection, rows) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .query_to_csv("SELECT 1 AS Id, N'a,b' AS Name", (), &mut csv)
+    ///         .await?;
+    ///
+    ///     assert_eq!(1, rows);
+    ///     assert_eq!("Id,Name\r\n1,\"a,b\"\r\n", String::from_utf8(csv).unwrap());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_to_csv<'a, S, P, W>(
+        self,
+        sql: S,
+        params: P,
+        mut writer: W,
+    ) -> LocalBoxFuture<'a, Result<(Self, u64)>>
+    where
+        P: Params<'a> + 'a,
+        S: Into<Cow<'static, str>>,
+        W: AsyncWrite + Unpin + 'a,
+    {
+        let sql = sql.into();
+        let mut p = Vec::new();
+        params.params(&mut p);
+
+        Box::pin(async move {
+            let (conn, names) = column_names(self, &sql, &p).await?;
+            let mut line = String::new();
+
+            for (i, name) in names.iter().enumerate() {
+                push_csv(&mut line, i, Some(name));
+            }
+
+            line.push_str("\r\n");
+            writer.write_all(line.as_bytes()).await?;
+            let mut decoders = Decoders::default();
+
+            let (conn, (count, mut writer)) = conn
+                .query_fold_async(sql, p, (0u64, writer), move |(count, mut writer), row| {
+                    let line = csv_line(&mut decoders, &row);
+
+                    async move {
+                        writer.write_all(line?.as_bytes()).await?;
+                        Ok::<_, Error>((count + 1, writer))
+                    }
+                })
+                .await?;
+
+            writer.flush().await?;
+            Ok((conn, count))
+        })
+    }
+
+    /// Runs a query and writes its rows to `writer` as a JSON array of objects keyed by
+    /// column name. Returns the number of rows written.
+    ///
+    /// The rows are written as they are received. Numbers and booleans are written as
+    /// JSON values, except infinite floats which are written as `null`; binary values are
+    /// written as hexadecimal strings (`0x...`) and dates as ISO 8601 strings.
+    ///
+    /// See [query_to_csv](#method.query_to_csv) for the writer and how the column names are
+    /// read, with one more round trip.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut json = Vec::new();
+    ///     Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .query_to_json("SELECT 1 AS Id, NULL AS Name", (), &mut json)
+    ///         .await?;
+    ///
+    ///     assert_eq!("[\n{\"Id\":1,\"Name\":null}\n]\n", String::from_utf8(json).unwrap());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_to_json<'a, S, P, W>(
+        self,
+        sql: S,
+        params: P,
+        mut writer: W,
+    ) -> LocalBoxFuture<'a, Result<(Self, u64)>>
+    where
+        P: Params<'a> + 'a,
+        S: Into<Cow<'static, str>>,
+        W: AsyncWrite + Unpin + 'a,
+    {
+        let sql = sql.into();
+        let mut p = Vec::new();
+        params.params(&mut p);
+
+        Box::pin(async move {
+            let (conn, names) = column_names(self, &sql, &p).await?;
+            writer.write_all(b"[").await?;
+            let mut decoders = Decoders::default();
+
+            // the writer is kept in the fold state to close the array afterward
+            let (conn, (count, mut writer)) = conn
+                .query_fold_async(sql, p, (0u64, writer), move |(count, mut writer), row| {
+                    let obj = json_object(&mut decoders, &names, &row, count == 0);
+
+                    async move {
+                        writer.write_all(obj?.as_bytes()).await?;
+                        Ok::<_, Error>((count + 1, writer))
+                    }
+                })
+                .await?;
+
+            writer.write_all(b"\n]\n").await?;
+            writer.flush().await?;
+            Ok((conn, count))
+        })
+    }
+
+    /// Runs a query and reads its rows as maps from the column names to the values as text,
+    /// `None` for null, to inspect the output of any query without declaring a type.
+    ///
+    /// The values are formatted as by [query_to_csv](#method.query_to_csv). The column
+    /// names are also read as by `query_to_csv`, which costs one more round trip and fails
+    /// for the statements using temporary tables created in the same batch.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let (_connection, rows) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .query_to_maps("SELECT 1 AS Id, NULL AS Name", ())
+    ///         .await?;
+    ///
+    ///     assert_eq!(Some("1"), rows[0]["Id"].as_deref());
+    ///     assert_eq!(None, rows[0]["Name"]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_to_maps<'a, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, Vec<HashMap<String, Option<String>>>)>>
+    where
+        P: Params<'a> + 'a,
+        S: Into<Cow<'static, str>>,
+    {
+        let sql = sql.into();
+        let mut p = Vec::new();
+        params.params(&mut p);
+
+        Box::pin(async move {
+            let (conn, names) = column_names(self, &sql, &p).await?;
+            let mut decoders = Decoders::default();
+
+            conn.query_map(sql, p, move |row| {
+                let values = decoders.read(row)?;
+
+                Ok(names
+                    .iter()
+                    .cloned()
+                    .zip(values.iter().map(to_text))
+                    .collect())
+            })
+            .await
+        })
+    }
+}
+
+/// Formats a row as a CSV line.
+fn csv_line(decoders: &mut Decoders, row: &Row) -> Result<String> {
+    let mut line = String::new();
+
+    for (i, value) in decoders.read(row)?.iter().enumerate() {
+        push_csv(&mut line, i, to_text(value).as_deref());
+    }
+
+    line.push_str("\r\n");
+    Ok(line)
+}
+
+/// Formats a row as a JSON object, preceded by the separator of the array.
+fn json_object(
+    decoders: &mut Decoders,
+    names: &[String],
+    row: &Row,
+    first: bool,
+) -> Result<String> {
+    let values = decoders.read(row)?;
+    let mut obj = String::from(if first { "\n{" } else { ",\n{" });
+
+    for (i, (name, value)) in names.iter().zip(values.iter()).enumerate() {
+        if i > 0 {
+            obj.push(',');
+        }
+
+        push_json_str(&mut obj, name);
+        obj.push(':');
+        push_json(&mut obj, value);
+    }
+
+    obj.push('}');
+    Ok(obj)
+}
+
+/// Reads the column names of the first result set of a statement, without running it.
+pub(crate) async fn column_names(
+    conn: Connection,
+    sql: &str,
+    params: &[Parameter<'_>],
+) -> Result<(Connection, Vec<String>)> {
+    let decl = params
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format!("@p{} {}", i + 1, sql_type(p)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let describe = r#"
+        SELECT name, error_message
+        FROM sys.dm_exec_describe_first_result_set(@p1, @p2, 0)
+        WHERE is_hidden = 0 OR error_message IS NOT NULL
+        ORDER BY column_ordinal"#;
+
+    let (conn, rows): (_, Vec<(Option<String>, Option<String>)>) =
+        conn.query(describe, (sql.to_owned(), decl)).await?;
+
+    if let Some(e) = rows.iter().find_map(|(_, e)| e.as_ref()) {
+        return Err(Error::String(format!("Could not describe the query: {}", e)));
+    }
+
+    let names = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, _))| name.unwrap_or_else(|| format!("Column{}", i + 1)))
+        .collect();
+
+    Ok((conn, names))
+}
+
+/// The type used to declare a parameter to `sys.dm_exec_describe_first_result_set`.
+fn sql_type(p: &Parameter) -> Cow<'static, str> {
+    Cow::Borrowed(match p {
+        Parameter::AnsiString(_) => "VARCHAR(MAX)",
+        Parameter::Binary(_) => "VARBINARY(MAX)",
+        Parameter::Bool(_) => "BIT",
+        Parameter::Date(_) => "DATE",
+        Parameter::DateTime(_) => "DATETIME2",
+        Parameter::F32(_) => "REAL",
+        Parameter::F64(_) => "FLOAT",
+        Parameter::I16(_) => "SMALLINT",
+        Parameter::I32(_) => "INT",
+        Parameter::I64(_) => "BIGINT",
+        Parameter::String(_) => "NVARCHAR(MAX)",
+        Parameter::Typed(_, _) => return p.declared_type(),
+        Parameter::Uuid(_) => "UNIQUEIDENTIFIER",
+    })
+}
+
+/// Formats a value as text, `None` for null.
+fn to_text(v: &SqlData) -> Option<String> {
+    Some(match v {
+        SqlData::Null => return None,
+        SqlData::Binary(b) => b.iter().fold(String::from("0x"), |mut s, b| {
+            let _ = write!(s, "{:02X}", b);
+            s
+        }),
+        SqlData::Bool(v) => v.to_string(),
+        SqlData::Date(v) => v.to_string(),
+        SqlData::DateTime(v) => v.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        SqlData::Decimal(v) => v.to_string(),
+        SqlData::F32(v) => v.to_string(),
+        SqlData::F64(v) => v.to_string(),
+        SqlData::I16(v) => v.to_string(),
+        SqlData::I32(v) => v.to_string(),
+        SqlData::I64(v) => v.to_string(),
+        SqlData::U8(v) => v.to_string(),
+        SqlData::String(v) => v.clone(),
+        SqlData::Uuid(v) => v.to_string(),
+    })
+}
+
+/// Appends a CSV field, quoted if it contains a separator, a quote or a line break.
+fn push_csv(out: &mut String, index: usize, value: Option<&str>) {
+    if index > 0 {
+        out.push(',');
+    }
+
+    let value = value.unwrap_or_default();
+
+    if value.contains(|c| c == ',' || c == '"' || c == '\r' || c == '\n') {
+        out.push('"');
+        out.push_str(&value.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(value);
+    }
+}
+
+fn push_json(out: &mut String, v: &SqlData) {
+    match v {
+        SqlData::F32(f) if !f.is_finite() => out.push_str("null"),
+        SqlData::F64(f) if !f.is_finite() => out.push_str("null"),
+        SqlData::Bool(_)
+        | SqlData::Decimal(_)
+        | SqlData::F32(_)
+        | SqlData::F64(_)
+        | SqlData::I16(_)
+        | SqlData::I32(_)
+        | SqlData::I64(_)
+        | SqlData::U8(_) => out.push_str(&to_text(v).unwrap_or_default()),
+        _ => match to_text(v) {
+            Some(s) => push_json_str(out, &s),
+            None => out.push_str("null"),
+        },
+    }
+}
+
+fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+#[test]
+fn push_csv_works() {
+    let mut s = String::new();
+    push_csv(&mut s, 0, Some("a"));
+    push_csv(&mut s, 1, None);
+    push_csv(&mut s, 2, Some("b,\"c\""));
+    push_csv(&mut s, 3, Some("d\ne"));
+    assert_eq!("a,,\"b,\"\"c\"\"\",\"d\ne\"", s);
+}
+
+#[test]
+fn push_json_works() {
+    let mut s = String::new();
+    push_json(&mut s, &SqlData::String("a\"b\\c\n\u{1}".into()));
+    push_json(&mut s, &SqlData::F64(f64::NAN));
+    push_json(&mut s, &SqlData::Binary(vec![1, 171]));
+    push_json(&mut s, &SqlData::I32(-5));
+    assert_eq!(r#""a\"b\\c\n\u0001"null"0x01AB"-5"#, s);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn query_to_csv() -> Result<()> {
+        let mut csv = Vec::new();
+        let (_, rows) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query_to_csv("SELECT @p1 AS A, CAST(NULL AS INT) AS B, 0x01", 5, &mut csv)
+            .await?;
+
+        assert_eq!(1, rows);
+        assert_eq!("A,B,Column3\r\n5,,0x01\r\n", String::from_utf8(csv).unwrap());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_to_json() -> Result<()> {
+        let mut json = Vec::new();
+        let (_, rows) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query_to_json("SELECT 1 AS A WHERE 1 = 0", (), &mut json)
+            .await?;
+
+        assert_eq!(0, rows);
+        assert_eq!("[\n]\n", String::from_utf8(json).unwrap());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_to_maps() -> Result<()> {
+        let (_, rows) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query_to_maps("SELECT @p1 AS A, CAST(NULL AS INT) AS B, 0x01", 5)
+            .await?;
+
+        let row = &rows[0];
+        assert_eq!(3, row.len());
+        assert_eq!(Some("5"), row["A"].as_deref());
+        assert_eq!(None, row["B"]);
+        assert_eq!(Some("0x01"), row["Column3"].as_deref());
+        Ok(())
+    }
+}
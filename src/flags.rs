@@ -0,0 +1,82 @@
+/// Map a [bitflags](https://docs.rs/bitflags) type to an INT column.
+///
+/// This implements [FromColumn](trait.FromColumn.html) and [Params](trait.Params.html)
+/// for the type. Reading a value containing unknown bits returns an error.
+///
+/// Requires the `bitflags` feature.
+///
+/// # Example
+/// ```
+/// use bitflags::bitflags;
+/// use mssql_client::flags_column;
+///
+/// bitflags! {
+///     #[derive(Clone, Copy, Debug, PartialEq)]
+///     pub struct Permissions: i32 {
+///         const READ = 1;
+///         const WRITE = 2;
+///     }
+/// }
+///
+/// flags_column!(Permissions);
+/// ```
+#[macro_export]
+macro_rules! flags_column {
+    ($t:ty) => {
+        impl<'a> $crate::FromColumn<'a> for $t {
+            type Value = i32;
+
+            fn from_column(v: Self::Value) -> $crate::Result<Self> {
+                <$t as $crate::bitflags::Flags>::from_bits(v as _).ok_or_else(|| {
+                    $crate::Error::String(format!(
+                        "Invalid value `{}` for flags `{}`.",
+                        v,
+                        stringify!($t)
+                    ))
+                })
+            }
+        }
+
+        impl<'a> $crate::Params<'a> for $t {
+            fn params(self, out: &mut Vec<$crate::Parameter<'a>>) {
+                $crate::Params::params($crate::bitflags::Flags::bits(&self) as i32, out)
+            }
+
+            fn params_null(out: &mut Vec<$crate::Parameter<'a>>) {
+                <i32 as $crate::Params<'a>>::params_null(out)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FromColumn, Params};
+    use bitflags::bitflags;
+
+    bitflags! {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Permissions: i32 {
+            const READ = 1;
+            const WRITE = 2;
+        }
+    }
+
+    flags_column!(Permissions);
+
+    #[test]
+    fn from_column_works() {
+        assert_eq!(
+            Permissions::READ | Permissions::WRITE,
+            Permissions::from_column(3).unwrap()
+        );
+        assert!(Permissions::from_column(8).is_err());
+    }
+
+    #[test]
+    fn params_works() {
+        let mut out = Vec::new();
+        Permissions::WRITE.params(&mut out);
+        assert_eq!("2", format!("{:?}", out[0]));
+    }
+}
@@ -1,8 +1,11 @@
-use crate::{Result, SqlValue};
+use crate::{Error, Result, SqlValue};
 
 /// This trait convert a sql column value into a rust type.
 /// Implement this trait to be able to support more types as needed.
 ///
+/// For enums, the `FromColumnStr` and `FromColumnInt` derives of the `derive`
+/// feature generate the implementation below.
+///
 /// ```
 /// use mssql_client::{FromColumn, Result};
 ///
@@ -83,6 +86,21 @@ impl<'a> FromColumn<'a> for bool {
     }
 }
 
+/// Reads a `nchar(1)` or `nvarchar(1)` column, failing when the value is not exactly one
+/// character.
+impl<'a> FromColumn<'a> for char {
+    type Value = &'a str;
+
+    fn from_column(v: Self::Value) -> Result<Self> {
+        let mut chars = v.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(Error::String(format!("`{}` is not a single character.", v))),
+        }
+    }
+}
+
 impl<'a> FromColumn<'a> for chrono::NaiveDate {
     type Value = chrono::NaiveDate;
 
@@ -147,6 +165,15 @@ impl<'a> FromColumn<'a> for i8 {
     }
 }
 
+/// Reads a `tinyint` column, which is unsigned.
+impl<'a> FromColumn<'a> for u8 {
+    type Value = u8;
+
+    fn from_column(v: Self::Value) -> Result<Self> {
+        Ok(v)
+    }
+}
+
 impl<'a> FromColumn<'a> for &'a [u8] {
     type Value = &'a [u8];
 
@@ -162,3 +189,10 @@ impl<'a> FromColumn<'a> for &'a str {
         Ok(v)
     }
 }
+
+#[test]
+fn char_works() {
+    assert_eq!('A', char::from_column("A").unwrap());
+    assert!(char::from_column("").is_err());
+    assert!(char::from_column("AB").is_err());
+}
@@ -6,6 +6,56 @@ pub trait FromRow {
         Self: Sized;
 }
 
+/// Reads a type from contiguous columns starting at an offset, so that it can be nested in
+/// the [FromRow](trait.FromRow.html) of a row covering many joined tables.
+///
+/// It is implemented by `#[derive(FromRow)]`, whose `#[from_row(flatten)]` fields are read
+/// with this trait, the following fields starting after their `COLUMNS`.
+///
+/// # Example
+/// ```
+/// use mssql_client::{FromRow, FromRowOffset, Result, Row};
+///
+/// struct Customer {
+///     id: i32,
+///     name: String,
+/// }
+///
+/// impl FromRowOffset for Customer {
+///     const COLUMNS: usize = 2;
+///
+///     fn from_row_offset(row: &Row, offset: usize) -> Result<Self> {
+///         Ok(Customer {
+///             id: row.get(offset)?,
+///             name: row.get(offset + 1)?,
+///         })
+///     }
+/// }
+///
+/// struct Order {
+///     id: i32,
+///     customer: Customer,
+///     total: f64,
+/// }
+///
+/// // SELECT o.Id, c.Id, c.Name, o.Total FROM Orders o JOIN Customers c ON ...
+/// impl FromRow for Order {
+///     fn from_row(row: &Row) -> Result<Self> {
+///         Ok(Order {
+///             id: row.get(0)?,
+///             customer: Customer::from_row_offset(row, 1)?,
+///             total: row.get(1 + Customer::COLUMNS)?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromRowOffset: Sized {
+    /// The number of columns read.
+    const COLUMNS: usize;
+
+    fn from_row_offset(row: &Row, offset: usize) -> Result<Self>;
+}
+
 impl<A> FromRow for A
 where
     A: for<'a> FromColumn<'a>,
@@ -0,0 +1,179 @@
+use crate::{Error, FromColumn, Result};
+use geo_types::{
+    Coordinate, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint,
+    MultiPolygon, Point, Polygon,
+};
+use std::convert::TryInto;
+
+/// A `geography` or `geometry` value read as [geo-types](https://docs.rs/geo-types).
+///
+/// The driver does not decode the spatial types, so the column must be selected as
+/// WKB with `STAsBinary()`. For `geography`, `x` is the longitude and `y` the latitude.
+///
+/// # Example
+/// ```
+/// use geo_types::Geometry;
+/// use mssql_client::{Connection, Geo, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let (_connection, rows): (_, Vec<Geo>) = Connection::from_env("MSSQL_DB")
+///         .await?
+///         .query("SELECT geography::Point(45.5, -73.6, 4326).STAsBinary()", ())
+///         .await?;
+///
+///     assert!(matches!(rows[0].0, Geometry::Point(_)));
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Geo(pub Geometry<f64>);
+
+impl Geo {
+    /// Parses a 2D OGC well-known binary value.
+    pub fn from_wkb(wkb: &[u8]) -> Result<Self> {
+        let mut reader = Wkb { bytes: wkb, le: true };
+        let geometry = reader.geometry()?;
+
+        if reader.bytes.is_empty() {
+            Ok(Geo(geometry))
+        } else {
+            Err(Error::Str("Unexpected trailing bytes in WKB."))
+        }
+    }
+}
+
+impl From<Geo> for Geometry<f64> {
+    fn from(v: Geo) -> Self {
+        v.0
+    }
+}
+
+impl<'a> FromColumn<'a> for Geo {
+    type Value = &'a [u8];
+
+    fn from_column(v: Self::Value) -> Result<Self> {
+        Self::from_wkb(v)
+    }
+}
+
+struct Wkb<'a> {
+    bytes: &'a [u8],
+    /// The byte order of the geometry being read.
+    le: bool,
+}
+
+impl<'a> Wkb<'a> {
+    fn geometry(&mut self) -> Result<Geometry<f64>> {
+        self.le = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::Str("Invalid WKB byte order.")),
+        };
+
+        Ok(match self.u32()? {
+            1 => Geometry::Point(self.point()?),
+            2 => Geometry::LineString(self.line_string()?),
+            3 => Geometry::Polygon(self.polygon()?),
+            4 => Geometry::MultiPoint(MultiPoint(self.many(Geometry::into_point)?)),
+            5 => Geometry::MultiLineString(MultiLineString(
+                self.many(Geometry::into_line_string)?,
+            )),
+            6 => Geometry::MultiPolygon(MultiPolygon(self.many(Geometry::into_polygon)?)),
+            7 => Geometry::GeometryCollection(GeometryCollection(self.many(Some)?)),
+            t => return Err(Error::String(format!("Unsupported WKB geometry type {}.", t))),
+        })
+    }
+
+    /// Reads the geometries of a multi geometry or a collection.
+    fn many<T, F>(&mut self, f: F) -> Result<Vec<T>>
+    where
+        F: Fn(Geometry<f64>) -> Option<T>,
+    {
+        (0..self.u32()?)
+            .map(|_| f(self.geometry()?).ok_or(Error::Str("Unexpected WKB geometry type.")))
+            .collect()
+    }
+
+    fn coordinate(&mut self) -> Result<Coordinate<f64>> {
+        Ok(Coordinate {
+            x: self.f64()?,
+            y: self.f64()?,
+        })
+    }
+
+    fn line_string(&mut self) -> Result<LineString<f64>> {
+        Ok(LineString(
+            (0..self.u32()?).map(|_| self.coordinate()).collect::<Result<_>>()?,
+        ))
+    }
+
+    fn point(&mut self) -> Result<Point<f64>> {
+        Ok(Point(self.coordinate()?))
+    }
+
+    fn polygon(&mut self) -> Result<Polygon<f64>> {
+        let mut rings = (0..self.u32()?)
+            .map(|_| self.line_string())
+            .collect::<Result<Vec<_>>>()?;
+
+        if rings.is_empty() {
+            return Ok(Polygon::new(LineString(Vec::new()), Vec::new()));
+        }
+
+        let exterior = rings.remove(0);
+        Ok(Polygon::new(exterior, rings))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        let b = self.take(8)?.try_into().expect("8 bytes");
+        Ok(if self.le { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) })
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take(4)?.try_into().expect("4 bytes");
+        Ok(if self.le { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(Error::Str("Unexpected end of WKB."));
+        }
+
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+}
+
+#[test]
+fn from_wkb_works() {
+    // POINT (1 2), little endian
+    let mut point = vec![1, 1, 0, 0, 0];
+    point.extend_from_slice(&1f64.to_le_bytes());
+    point.extend_from_slice(&2f64.to_le_bytes());
+
+    assert_eq!(
+        Geometry::Point(Point::new(1.0, 2.0)),
+        Geo::from_wkb(&point).unwrap().0
+    );
+
+    // LINESTRING (0 0, 3 4), big endian
+    let mut line = vec![0, 0, 0, 0, 2, 0, 0, 0, 2];
+
+    for v in &[0f64, 0.0, 3.0, 4.0] {
+        line.extend_from_slice(&v.to_be_bytes());
+    }
+
+    match Geo::from_wkb(&line).unwrap().0 {
+        Geometry::LineString(l) => assert_eq!(2, l.0.len()),
+        g => panic!("unexpected {:?}", g),
+    }
+
+    // MULTIPOINT holding the point
+    let mut multi = vec![1, 4, 0, 0, 0, 1, 0, 0, 0];
+    multi.extend_from_slice(&point);
+    assert!(matches!(Geo::from_wkb(&multi).unwrap().0, Geometry::MultiPoint(_)));
+
+    assert!(Geo::from_wkb(&point[..10]).is_err());
+}
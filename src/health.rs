@@ -0,0 +1,51 @@
+use crate::Result;
+use std::time::SystemTime;
+
+/// The outcome of the recent connection attempts of a
+/// [ConnectionFactory](struct.ConnectionFactory.html), see
+/// [ConnectionFactory::health](struct.ConnectionFactory.html#method.health).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Health {
+    /// The time of the last successful connection.
+    pub last_success: Option<SystemTime>,
+    /// The time and message of the last failed connection.
+    pub last_error: Option<(SystemTime, String)>,
+    /// The number of failed connections since the last successful one.
+    pub consecutive_failures: u32,
+}
+
+impl Health {
+    /// Returns `true` if a connection was made and the last attempt succeeded.
+    pub fn is_healthy(&self) -> bool {
+        self.last_success.is_some() && self.consecutive_failures == 0
+    }
+
+    pub(crate) fn record<T>(&mut self, result: &Result<T>) {
+        let now = SystemTime::now();
+
+        match result {
+            Ok(_) => {
+                self.last_success = Some(now);
+                self.consecutive_failures = 0;
+            }
+            Err(e) => {
+                self.last_error = Some((now, e.to_string()));
+                self.consecutive_failures += 1;
+            }
+        }
+    }
+}
+
+#[test]
+fn health_works() {
+    let mut health = Health::default();
+    assert!(!health.is_healthy());
+
+    health.record::<()>(&Err(crate::Error::Str("down")));
+    assert_eq!(1, health.consecutive_failures);
+    assert_eq!("down", health.last_error.as_ref().unwrap().1);
+
+    health.record(&Ok(()));
+    assert!(health.is_healthy());
+    assert!(health.last_error.is_some());
+}
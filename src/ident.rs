@@ -0,0 +1,201 @@
+use crate::{
+    utils::{quote_ident, split_name},
+    Error, Result,
+};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+/// A validated identifier, such as a database, schema, table or column name.
+///
+/// The identifier is stored unquoted and displays quoted with brackets, so it can be
+/// formatted into a statement without risk of injection.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Ident, Result};
+/// use std::convert::TryFrom;
+///
+/// fn main() -> Result<()> {
+///     let column = Ident::try_from("Order Date")?;
+///     assert_eq!("[Order Date]", column.to_string());
+///     assert!(Ident::try_from("").is_err());
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Ident(String);
+
+impl Ident {
+    /// Creates an identifier from its unquoted value.
+    ///
+    /// An error is returned if the value is empty, longer than the 128 characters of a
+    /// `sysname` or contains control characters.
+    pub fn new(value: &str) -> Result<Self> {
+        validate("identifier", value)?;
+        Ok(Self(value.to_owned()))
+    }
+
+    /// The unquoted value.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Ident {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Ident {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&quote_ident(&self.0))
+    }
+}
+
+impl FromStr for Ident {
+    type Err = Error;
+
+    /// Parses an identifier, optionally quoted with brackets.
+    fn from_str(s: &str) -> Result<Self> {
+        match split_name(s).as_slice() {
+            [value] => Self::new(value),
+            _ => Err(Error::String(format!("`{}` is not a single identifier.", s))),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Ident {
+    type Error = Error;
+
+    fn try_from(s: &'a str) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// A validated object name optionally qualified with a schema, `schema.name` or `name`.
+///
+/// An unqualified name is resolved by the server with the default schema of the user.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Result, SchemaIdent};
+/// use std::convert::TryFrom;
+///
+/// fn main() -> Result<()> {
+///     let orders = SchemaIdent::try_from("dbo.[Orders]")?;
+///     assert_eq!("[dbo].[Orders]", orders.to_string());
+///
+///     let sql = format!("SELECT COUNT(*) FROM {}", orders);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SchemaIdent {
+    schema: Option<Ident>,
+    name: Ident,
+}
+
+impl SchemaIdent {
+    pub fn new(schema: Option<Ident>, name: Ident) -> Self {
+        Self { schema, name }
+    }
+
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn schema(&self) -> Option<&Ident> {
+        self.schema.as_ref()
+    }
+
+    /// The name given to `OBJECT_ID`, which only sees temporary tables through `tempdb`.
+    pub(crate) fn object_name(&self) -> String {
+        match &self.schema {
+            None if self.name.0.starts_with('#') => format!("tempdb..{}", self.name),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl Display for SchemaIdent {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.schema {
+            Some(schema) => write!(f, "{}.{}", schema, self.name),
+            None => self.name.fmt(f),
+        }
+    }
+}
+
+impl From<Ident> for SchemaIdent {
+    fn from(name: Ident) -> Self {
+        Self { schema: None, name }
+    }
+}
+
+impl FromStr for SchemaIdent {
+    type Err = Error;
+
+    /// Parses a one or two-part name, each part optionally quoted with brackets.
+    fn from_str(s: &str) -> Result<Self> {
+        match split_name(s).as_slice() {
+            [name] => Ok(Self::new(None, Ident::new(name)?)),
+            [schema, name] => Ok(Self::new(Some(Ident::new(schema)?), Ident::new(name)?)),
+            _ => Err(Error::String(format!(
+                "`{}` is not a schema-qualified name (schema.name).",
+                s
+            ))),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for SchemaIdent {
+    type Error = Error;
+
+    fn try_from(s: &'a str) -> Result<Self> {
+        s.parse()
+    }
+}
+
+pub(crate) fn validate(part: &str, value: &str) -> Result<()> {
+    if value.is_empty() {
+        Err(Error::String(format!("The {} is empty.", part)))
+    } else if value.chars().count() > 128 {
+        Err(Error::String(format!("The {} `{}` is longer than 128 characters.", part, value)))
+    } else if value.chars().any(char::is_control) {
+        Err(Error::String(format!("The {} `{}` contains control characters.", part, value)))
+    } else {
+        Ok(())
+    }
+}
+
+#[test]
+fn ident_works() {
+    let i: Ident = "[My]]Col]".parse().unwrap();
+    assert_eq!("My]Col", i.as_str());
+    assert_eq!("[My]]Col]", i.to_string());
+
+    assert!(Ident::try_from("a.b").is_err());
+    assert!(Ident::new("a\0b").is_err());
+    assert!(Ident::new(&"x".repeat(129)).is_err());
+}
+
+#[test]
+fn schema_ident_works() {
+    let o = SchemaIdent::try_from("dbo.Orders").unwrap();
+    assert_eq!("dbo", o.schema().unwrap().as_str());
+    assert_eq!("[dbo].[Orders]", o.to_string());
+    assert_eq!("[dbo].[Orders]", o.object_name());
+    assert_eq!(o, "[dbo].[Orders]".parse().unwrap());
+    assert_eq!("[Odd]]Name]", SchemaIdent::try_from("[Odd]]Name]").unwrap().to_string());
+
+    let t = SchemaIdent::try_from("#Temp").unwrap();
+    assert_eq!("[#Temp]", t.to_string());
+    assert_eq!("tempdb..[#Temp]", t.object_name());
+
+    assert!(SchemaIdent::try_from("db.dbo.Orders").is_err());
+    assert!(SchemaIdent::try_from("dbo.").is_err());
+}
@@ -0,0 +1,144 @@
+use crate::{Params, Result, Transaction};
+use futures03::future::LocalBoxFuture;
+use std::{borrow::Cow, fmt::Debug};
+
+/// The outcome of [Transaction::import_rows](struct.Transaction.html#method.import_rows).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    /// The number of rows written.
+    pub imported: usize,
+    /// The rows rolled back, in input order.
+    pub errors: Vec<RowError>,
+}
+
+/// A row rolled back by [Transaction::import_rows](struct.Transaction.html#method.import_rows).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowError {
+    /// The zero-based position of the row in the input.
+    pub row: usize,
+    /// The sql server error number.
+    pub number: i32,
+    pub message: String,
+}
+
+impl Transaction {
+    /// Executes `sql` once per row of parameters, each under its own savepoint.
+    ///
+    /// A row that fails is rolled back to its savepoint and recorded in the report
+    /// while the other rows stay in the transaction, ready to be committed. The
+    /// statement must not return rows.
+    ///
+    /// Errors that doom the transaction (`XACT_ABORT ON`, some conversion errors)
+    /// and compile errors cannot be isolated; they are returned and the transaction
+    /// is lost, as with [execute](#method.execute).
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let tx = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .execute("CREATE TABLE #T (Id INT PRIMARY KEY, Name NVARCHAR(10))", ())
+    ///         .await?
+    ///         .transaction()
+    ///         .await?;
+    ///
+    ///     let rows = vec![(1, "a"), (1, "duplicate"), (2, "b")];
+    ///     let (tx, report) = tx
+    ///         .import_rows("INSERT INTO #T VALUES (@p1, @p2)", rows)
+    ///         .await?;
+    ///
+    ///     tx.commit().await?;
+    ///     assert_eq!(2, report.imported);
+    ///     assert_eq!(1, report.errors[0].row);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn import_rows<'a, S, P, I>(
+        self,
+        sql: S,
+        rows: I,
+    ) -> LocalBoxFuture<'a, Result<(Self, ImportReport)>>
+    where
+        I: IntoIterator<Item = P> + 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Into<Cow<'static, str>>,
+    {
+        let sql = import_sql(&sql.into());
+
+        Box::pin(async move {
+            let mut tx = self;
+            let mut report = ImportReport::default();
+
+            for (row, params) in rows.into_iter().enumerate() {
+                let (t, errors): (_, Vec<(i32, String)>) = tx.query(sql.clone(), params).await?;
+                tx = t;
+
+                match errors.into_iter().next() {
+                    Some((number, message)) => report.errors.push(RowError {
+                        row,
+                        number,
+                        message,
+                    }),
+                    None => report.imported += 1,
+                }
+            }
+
+            Ok((tx, report))
+        })
+    }
+}
+
+/// Wraps a statement in a savepoint, returning the error instead of failing the batch.
+fn import_sql(sql: &str) -> String {
+    format!(
+        r#"SAVE TRANSACTION import_row;
+BEGIN TRY
+{}
+END TRY
+BEGIN CATCH
+    IF XACT_STATE() = -1 THROW;
+    ROLLBACK TRANSACTION import_row;
+    SELECT ERROR_NUMBER(), ERROR_MESSAGE();
+END CATCH"#,
+        sql
+    )
+}
+
+#[test]
+fn import_sql_works() {
+    let sql = import_sql("INSERT INTO T VALUES (@p1)");
+
+    assert!(sql.starts_with("SAVE TRANSACTION import_row;"));
+    assert!(sql.contains("BEGIN TRY\nINSERT INTO T VALUES (@p1)\nEND TRY"));
+    assert!(sql.contains("ROLLBACK TRANSACTION import_row;"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+
+    #[tokio::test]
+    async fn import_rows() -> Result<()> {
+        let tx = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #Import (Id INT PRIMARY KEY, V INT CHECK (V > 0))", ())
+            .await?
+            .transaction()
+            .await?;
+
+        let rows = vec![(1, 1), (2, -1), (1, 3), (4, 4)];
+        let (tx, report) = tx.import_rows("INSERT INTO #Import VALUES (@p1, @p2)", rows).await?;
+        let (_, ids): (_, Vec<i32>) = tx.query("SELECT Id FROM #Import ORDER BY Id", ()).await?;
+
+        assert_eq!(2, report.imported);
+        assert_eq!(vec![1, 2], report.errors.iter().map(|e| e.row).collect::<Vec<_>>());
+        assert_eq!(547, report.errors[0].number);
+        assert_eq!(2627, report.errors[1].number);
+        assert_eq!(vec![1, 4], ids);
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{utils::quote_ident, Connection, Error, Result};
+use futures03::future::LocalBoxFuture;
+
+/// The row versioning options of a database, as reported by
+/// [Connection::isolation_capabilities](struct.Connection.html#method.isolation_capabilities).
+#[derive(Clone, Debug, PartialEq)]
+pub struct IsolationCapabilities {
+    pub database: String,
+    /// `READ COMMITTED` reads row versions instead of taking shared locks (RCSI).
+    pub read_committed_snapshot: bool,
+    /// Transactions can use the `SNAPSHOT` isolation level.
+    ///
+    /// This is `false` while the option is being turned on or off.
+    pub snapshot_isolation: bool,
+}
+
+impl IsolationCapabilities {
+    /// Returns an error explaining how to enable `READ_COMMITTED_SNAPSHOT` if it is off.
+    pub fn require_read_committed_snapshot(&self) -> Result<()> {
+        if self.read_committed_snapshot {
+            Ok(())
+        } else {
+            Err(Error::String(format!(
+                "READ_COMMITTED_SNAPSHOT is off for database `{}`, readers will block on writers. \
+                Enable it with `ALTER DATABASE {} SET READ_COMMITTED_SNAPSHOT ON`.",
+                self.database,
+                quote_ident(&self.database)
+            )))
+        }
+    }
+
+    /// Returns an error explaining how to enable `ALLOW_SNAPSHOT_ISOLATION` if it is off.
+    pub fn require_snapshot_isolation(&self) -> Result<()> {
+        if self.snapshot_isolation {
+            Ok(())
+        } else {
+            Err(Error::String(format!(
+                "Snapshot isolation is not allowed for database `{}`. \
+                Enable it with `ALTER DATABASE {} SET ALLOW_SNAPSHOT_ISOLATION ON`.",
+                self.database,
+                quote_ident(&self.database)
+            )))
+        }
+    }
+}
+
+impl Connection {
+    /// Reports whether the current database uses read committed snapshot (RCSI) and
+    /// allows snapshot isolation, from `sys.databases`.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let (connection, caps) = connection.isolation_capabilities().await?;
+    ///
+    ///     // fail fast instead of blocking readers behind writers
+    ///     caps.require_read_committed_snapshot()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn isolation_capabilities(
+        self,
+    ) -> LocalBoxFuture<'static, Result<(Self, IsolationCapabilities)>> {
+        // snapshot_isolation_state: 0 off, 1 on, 2 turning off, 3 turning on
+        let sql = r#"
+            SELECT name, is_read_committed_snapshot_on, CAST(snapshot_isolation_state AS INT)
+            FROM sys.databases
+            WHERE database_id = DB_ID()"#;
+
+        Box::pin(async move {
+            let (conn, rows) = self
+                .query_map(sql, (), |row| {
+                    let state: i32 = row.get(2)?;
+
+                    Ok(IsolationCapabilities {
+                        database: row.get(0)?,
+                        read_committed_snapshot: row.get(1)?,
+                        snapshot_isolation: state == 1,
+                    })
+                })
+                .await?;
+
+            match rows.into_iter().next() {
+                Some(caps) => Ok((conn, caps)),
+                None => Err(Error::Str("Current database not found in sys.databases.")),
+            }
+        })
+    }
+}
+
+#[test]
+fn require_works() {
+    let caps = IsolationCapabilities {
+        database: "Sales".into(),
+        read_committed_snapshot: true,
+        snapshot_isolation: false,
+    };
+
+    assert!(caps.require_read_committed_snapshot().is_ok());
+    assert!(caps.require_snapshot_isolation().is_err());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn isolation_capabilities() -> Result<()> {
+        let (connection, caps) = Connection::from_env("MSSQL_DB")
+            .await?
+            .isolation_capabilities()
+            .await?;
+
+        assert_eq!(connection.database(), Some(caps.database.as_str()));
+        Ok(())
+    }
+}
@@ -4,29 +4,100 @@ mod from_row;
 #[macro_use]
 mod execute_sql;
 
+#[cfg(feature = "bitflags")]
+#[macro_use]
+mod flags;
+
+mod ansi;
+mod app_lock;
+mod blob;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod change_tracking;
 mod command;
 mod connection;
 mod connection_factory;
+mod context;
+mod copy_table;
+mod db_object;
+mod diagnose;
+mod dyn_command;
+mod dynamic_row;
 pub mod error;
+mod export;
 mod from_column;
+#[cfg(feature = "geo")]
+mod geo;
+mod health;
+mod ident;
+mod import;
+mod isolation;
+mod merge;
+mod money;
+mod observer;
+pub mod outbox;
 mod parameter;
 mod params;
+mod partition;
+mod query_builder;
 pub mod result;
 mod row;
+pub mod schema;
+mod seeder;
+mod session;
+mod shared_connection;
 mod sql_value;
+mod system_procs;
+#[cfg(feature = "time")]
+mod time_types;
 mod transaction;
+mod trimmed_string;
 mod utils;
+pub mod uuid_ext;
 
+pub use ansi::Ansi;
+pub use app_lock::{AppLock, LockMode};
+#[cfg(feature = "chaos")]
+pub use chaos::Chaos;
+pub use change_tracking::{Change, ChangeOperation};
 pub use command::Command;
 pub use connection::Connection;
 pub use connection_factory::ConnectionFactory;
+pub use context::{ExecutionMode, IdlePolicy};
+pub use copy_table::{copy_table, CopyOptions};
+pub use db_object::DbObject;
+pub use diagnose::{Diagnosis, Stage};
+pub use dyn_command::DynCommand;
+pub use dynamic_row::{DynamicRow, DynamicRows, SqlData};
 pub use error::Error;
 pub use from_column::FromColumn;
-pub use from_row::FromRow;
-pub use parameter::Parameter;
+#[cfg(feature = "derive")]
+pub use mssql_client_derive::{FromColumnInt, FromColumnStr, FromRow};
+pub use from_row::{FromRow, FromRowOffset};
+#[cfg(feature = "geo")]
+pub use geo::Geo;
+pub use health::Health;
+pub use ident::{Ident, SchemaIdent};
+pub use import::{ImportReport, RowError};
+pub use isolation::IsolationCapabilities;
+pub use merge::{MergeCounts, MergeOptions};
+pub use money::Money;
+pub use observer::{ConnectionEvent, QueryObserver};
+pub use parameter::{LengthPolicy, Parameter, SqlType};
 pub use params::*;
+pub use partition::Partition;
+pub use query_builder::QueryBuilder;
 pub use result::Result;
-pub use row::Row;
+pub use row::{FromProjection, Projection, Row};
+pub use seeder::Seeder;
+pub use session::Session;
+pub use shared_connection::SharedConnection;
 pub use sql_value::SqlValue;
-pub use transaction::Transaction;
+pub use system_procs::{SessionInfo, SpaceUsed};
+pub use transaction::{Transaction, TransactionOptions};
+pub use trimmed_string::TrimmedString;
 pub use utils::*;
+
+#[cfg(feature = "bitflags")]
+#[doc(hidden)]
+pub use bitflags;
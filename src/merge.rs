@@ -0,0 +1,306 @@
+use crate::{
+    copy_table::rows_per_insert,
+    utils::quote_ident,
+    Connection, Error, Parameter, Params, Result, SchemaIdent,
+};
+use futures03::future::LocalBoxFuture;
+use tracing::instrument;
+
+/// Options of [Connection::merge_rows](struct.Connection.html#method.merge_rows).
+#[derive(Clone, Debug, Default)]
+pub struct MergeOptions {
+    delete_missing: bool,
+}
+
+impl MergeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deletes the rows of the table that are not among the merged rows, `false` by default.
+    pub fn delete_missing(mut self, delete_missing: bool) -> Self {
+        self.delete_missing = delete_missing;
+        self
+    }
+}
+
+/// The rows changed by [Connection::merge_rows](struct.Connection.html#method.merge_rows).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MergeCounts {
+    pub inserted: i64,
+    pub updated: i64,
+    pub deleted: i64,
+}
+
+impl Connection {
+    /// Inserts or updates rows of a table matched on key columns, using a `MERGE` from a
+    /// temporary table loaded with the rows.
+    ///
+    /// Each row gives one value per column of `columns`, which must include the `keys`.
+    /// Matched rows are updated only if a value differs. The table must not have triggers
+    /// relying on single-row statements, and identity columns cannot be merged.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, MergeOptions, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let customers = "dbo.Customers".parse()?;
+    ///     let rows = vec![(1, "Foo"), (2, "Bar")];
+    ///
+    ///     let (connection, counts) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .merge_rows(&customers, &["Id"], &["Id", "Name"], rows, MergeOptions::new())
+    ///         .await?;
+    ///
+    ///     println!("{} inserted, {} updated", counts.inserted, counts.updated);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn merge_rows<'a, P, I>(
+        self,
+        table: &'a SchemaIdent,
+        keys: &'a [&'a str],
+        columns: &'a [&'a str],
+        rows: I,
+        options: MergeOptions,
+    ) -> LocalBoxFuture<'a, Result<(Self, MergeCounts)>>
+    where
+        I: IntoIterator<Item = P>,
+        P: Params<'a>,
+    {
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let mut p = Vec::new();
+                row.params(&mut p);
+                p
+            })
+            .collect();
+
+        Box::pin(self.merge_rows_imp(table, keys, columns, rows, options))
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "Connection::merge_rows",
+        skip(self, rows),
+        err
+    )]
+    async fn merge_rows_imp<'a>(
+        self,
+        table: &'a SchemaIdent,
+        keys: &'a [&'a str],
+        columns: &'a [&'a str],
+        rows: Vec<Vec<Parameter<'a>>>,
+        options: MergeOptions,
+    ) -> Result<(Self, MergeCounts)> {
+        check_keys(keys, columns)?;
+
+        if let Some(row) = rows.iter().find(|r| r.len() != columns.len()) {
+            return Err(Error::String(format!(
+                "Merge row has {} values for {} columns.",
+                row.len(),
+                columns.len()
+            )));
+        }
+
+        let list = columns.iter().map(|c| quote_ident(c)).collect::<Vec<_>>().join(", ");
+
+        // the UNION drops the identity property that SELECT INTO would copy
+        let create = format!(
+            r#"
+            IF OBJECT_ID('tempdb..#merge_rows') IS NOT NULL DROP TABLE #merge_rows;
+            SELECT TOP 0 {0} INTO #merge_rows FROM {1} UNION ALL SELECT TOP 0 {0} FROM {1}"#,
+            list, table
+        );
+
+        let mut conn = self.execute(create, ()).await?;
+        let insert = format!("INSERT INTO #merge_rows ({}) VALUES ", list);
+        let rows_per_insert = rows_per_insert(columns.len());
+        let mut rows = rows.into_iter().peekable();
+
+        while rows.peek().is_some() {
+            let chunk = rows.by_ref().take(rows_per_insert).collect::<Vec<_>>();
+            let sql = format!("{}{}", insert, values_sql(columns.len(), chunk.len()));
+            conn = conn.execute(sql, chunk.into_iter().flatten().collect::<Vec<_>>()).await?;
+        }
+
+        let sql = merge_sql(table, keys, columns, &options);
+        let (conn, counts): (_, Vec<(i64, i64, i64)>) = conn.query(sql, ()).await?;
+
+        let counts = counts
+            .into_iter()
+            .next()
+            .map(|(inserted, updated, deleted)| MergeCounts {
+                inserted,
+                updated,
+                deleted,
+            })
+            .unwrap_or_default();
+
+        Ok((conn, counts))
+    }
+}
+
+/// The `VALUES` rows of a multi-row insert, `(@p1, @p2), (@p3, @p4)`.
+fn values_sql(columns: usize, rows: usize) -> String {
+    (0..rows)
+        .map(|r| {
+            let params = (1..=columns)
+                .map(|c| format!("@p{}", r * columns + c))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!("({})", params)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks that there is at least one key and that the keys are among the columns.
+fn check_keys(keys: &[&str], columns: &[&str]) -> Result<()> {
+    if keys.is_empty() || keys.iter().any(|k| !columns.contains(k)) {
+        return Err(Error::Str("The merge keys must be among the columns."));
+    }
+
+    Ok(())
+}
+
+fn merge_sql(
+    table: &SchemaIdent,
+    keys: &[&str],
+    columns: &[&str],
+    options: &MergeOptions,
+) -> String {
+    let join = |v: Vec<String>, sep: &str| v.join(sep);
+    let q = |c: &&str| quote_ident(c);
+
+    let on = join(
+        keys.iter().map(|k| format!("t.{0} = s.{0}", q(k))).collect(),
+        " AND ",
+    );
+
+    let others = columns.iter().copied().filter(|c| !keys.contains(c)).collect::<Vec<_>>();
+
+    let mut sql = format!(
+        "DECLARE @actions TABLE (a NVARCHAR(10));\n\
+        MERGE {} WITH (HOLDLOCK) AS t\n\
+        USING #merge_rows AS s ON {}\n",
+        table, on
+    );
+
+    if !others.is_empty() {
+        let s_cols = join(others.iter().map(|c| format!("s.{}", q(c))).collect(), ", ");
+        let t_cols = join(others.iter().map(|c| format!("t.{}", q(c))).collect(), ", ");
+        let set = join(others.iter().map(|c| format!("t.{0} = s.{0}", q(c))).collect(), ", ");
+
+        sql.push_str(&format!(
+            "WHEN MATCHED AND EXISTS (SELECT {} EXCEPT SELECT {}) THEN UPDATE SET {}\n",
+            s_cols, t_cols, set
+        ));
+    }
+
+    let list = join(columns.iter().map(q).collect(), ", ");
+    let values = join(columns.iter().map(|c| format!("s.{}", q(c))).collect(), ", ");
+
+    sql.push_str(&format!(
+        "WHEN NOT MATCHED BY TARGET THEN INSERT ({}) VALUES ({})\n",
+        list, values
+    ));
+
+    if options.delete_missing {
+        sql.push_str("WHEN NOT MATCHED BY SOURCE THEN DELETE\n");
+    }
+
+    sql.push_str(
+        r#"OUTPUT $action INTO @actions;
+SELECT
+    COUNT_BIG(CASE a WHEN 'INSERT' THEN 1 END),
+    COUNT_BIG(CASE a WHEN 'UPDATE' THEN 1 END),
+    COUNT_BIG(CASE a WHEN 'DELETE' THEN 1 END)
+FROM @actions;
+DROP TABLE #merge_rows;"#,
+    );
+
+    sql
+}
+
+#[test]
+fn values_sql_works() {
+    assert_eq!("(@p1, @p2), (@p3, @p4)", values_sql(2, 2));
+}
+
+#[test]
+fn check_keys_works() {
+    assert!(check_keys(&["Id"], &["Id", "Name"]).is_ok());
+    assert!(check_keys(&[], &["Id", "Name"]).is_err());
+    assert!(check_keys(&["Id"], &[]).is_err());
+    assert!(check_keys(&["Code"], &["Id", "Name"]).is_err());
+}
+
+#[test]
+fn merge_sql_works() {
+    let table = "dbo.T".parse().unwrap();
+    let options = MergeOptions::new().delete_missing(true);
+    let sql = merge_sql(&table, &["Id"], &["Id", "Name"], &options);
+
+    assert!(sql.contains("MERGE [dbo].[T] WITH (HOLDLOCK) AS t"));
+    assert!(sql.contains("USING #merge_rows AS s ON t.[Id] = s.[Id]"));
+    assert!(sql.contains("UPDATE SET t.[Name] = s.[Name]"));
+    assert!(sql.contains("INSERT ([Id], [Name]) VALUES (s.[Id], s.[Name])"));
+    assert!(sql.contains("WHEN NOT MATCHED BY SOURCE THEN DELETE"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn merge_rows() -> Result<()> {
+        let table = "#Merge".parse()?;
+        let connection = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #Merge (Id INT PRIMARY KEY, Name NVARCHAR(10))", ())
+            .await?
+            .execute("INSERT INTO #Merge VALUES (1, 'a'), (2, 'b'), (3, 'c')", ())
+            .await?;
+
+        let rows = vec![(1, "a"), (2, "x"), (4, "d")];
+        let options = MergeOptions::new().delete_missing(true);
+
+        let (_, counts) = connection
+            .merge_rows(&table, &["Id"], &["Id", "Name"], rows, options)
+            .await?;
+
+        assert_eq!(
+            MergeCounts {
+                inserted: 1,
+                updated: 1,
+                deleted: 1
+            },
+            counts
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_rows_max_params() -> Result<()> {
+        let table = "#Merge".parse()?;
+        let connection = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #Merge (Id INT PRIMARY KEY, A INT, B INT)", ())
+            .await?;
+
+        // 3 columns, one more row than fits in an insert of 2100 parameters
+        let rows = (0..701).map(|i| (i, i, i)).collect::<Vec<_>>();
+
+        let (_, counts) = connection
+            .merge_rows(&table, &["Id"], &["Id", "A", "B"], rows, MergeOptions::new())
+            .await?;
+
+        assert_eq!(701, counts.inserted);
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{FromColumn, Parameter, Params, Result};
+use decimal::Decimal;
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+};
+
+/// A `money` or `smallmoney` value, with 4 decimals.
+///
+/// Values are bound as a string, which the server converts to `money` without going
+/// through a float, so they are sent exactly.
+///
+/// The driver decodes `money` as a `f64`, which is rounded back to 4 decimals. The values
+/// read are exact between -900,719,925,474.0991 and 900,719,925,474.0991, the values
+/// holding in 53 bits once scaled by 10,000, the others may be off in their last
+/// decimals. `smallmoney` is decoded as a `f32`, so only its first 7 significant digits
+/// are exact.
+///
+/// # Example
+/// ```
+/// use decimal::Decimal;
+/// use mssql_client::{Connection, Money, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let (_connection, rows): (_, Vec<Money>) = Connection::from_env("MSSQL_DB")
+///         .await?
+///         .query("SELECT CAST(1234.5678 AS MONEY)", ())
+///         .await?;
+///
+///     assert_eq!(Money(Decimal::new_with_scale(12_345_678, 4)), rows[0]);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Money(pub Decimal);
+
+impl Money {
+    pub(crate) fn from_f64(v: f64) -> Self {
+        Money(Decimal::new_with_scale((v * 10_000.0).round() as i128, 4))
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<Decimal> for Money {
+    fn from(v: Decimal) -> Self {
+        Money(v)
+    }
+}
+
+impl From<Money> for Decimal {
+    fn from(v: Money) -> Self {
+        v.0
+    }
+}
+
+impl<'a> FromColumn<'a> for Money {
+    type Value = Money;
+
+    fn from_column(v: Self::Value) -> Result<Self> {
+        Ok(v)
+    }
+}
+
+impl<'a> Params<'a> for Money {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(Some(Cow::Owned(self.to_string()))))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+
+    #[tokio::test]
+    async fn money() -> Result<()> {
+        let value = Money(Decimal::new_with_scale(922_337_203_685_477i128, 4));
+
+        let (_connection, rows): (_, Vec<(Money, Money)>) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query("SELECT CAST(@p1 AS MONEY), CAST(12.3456 AS SMALLMONEY)", value)
+            .await?;
+
+        assert_eq!(value, rows[0].0);
+        assert_eq!(Money(Decimal::new_with_scale(123_456i128, 4)), rows[0].1);
+
+        // the limit of the values read exactly, 2^53 - 1 scaled by 10,000
+        let value = Money(Decimal::new_with_scale(-9_007_199_254_740_991i128, 4));
+
+        let (_connection, rows): (_, Vec<Money>) = Connection::from_env("MSSQL_DB")
+            .await?
+            .query("SELECT CAST(@p1 AS MONEY)", value)
+            .await?;
+
+        assert_eq!(value, rows[0]);
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{Error, Parameter};
+use std::time::Duration;
+
+/// Receives an event for each sql statement sent to the database.
+///
+/// Set an observer using [ConnectionFactory::with_observer](struct.ConnectionFactory.html#method.with_observer)
+/// or [Connection::with_observer](struct.Connection.html#method.with_observer) to collect metrics,
+/// log slow queries or audit the sql without wrapping every call site.
+/// Transactions use the observer of their connection.
+///
+/// All methods do nothing by default.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Parameter, QueryObserver};
+/// use std::time::Duration;
+///
+/// struct Logger;
+///
+/// impl QueryObserver for Logger {
+///     fn on_start(&self, sql: &str, _params: &[Parameter]) {
+///         println!("executing {}", sql);
+///     }
+///
+///     fn on_complete(&self, duration: Duration, rows: u64) {
+///         println!("{} rows in {:?}", rows, duration);
+///     }
+/// }
+/// ```
+pub trait QueryObserver: Send + Sync {
+    /// Called before a statement is sent.
+    fn on_start(&self, _sql: &str, _params: &[Parameter]) {}
+
+    /// Called when a statement succeeds, with the number of rows read by a
+    /// query or the number of rows affected by an execute.
+    fn on_complete(&self, _duration: Duration, _rows: u64) {}
+
+    /// Called when a statement fails, including when it is rejected before being sent, by
+    /// the [strict_params](struct.ConnectionFactory.html#method.strict_params) check for
+    /// example.
+    fn on_error(&self, _error: &Error) {}
+}
+
+/// A change in the lifecycle of a connection, with the `@@SPID` of its session.
+///
+/// See [ConnectionFactory::on_connection_event](struct.ConnectionFactory.html#method.on_connection_event).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConnectionEvent {
+    /// The connection was opened and its session initialized.
+    Opened { spid: i16 },
+    /// The connection was closed with [Connection::close](struct.Connection.html#method.close)
+    /// or dropped, along with the transaction holding it if any.
+    Closed { spid: i16 },
+    /// A statement failed, the connection is then dropped and reported closed.
+    Broken { spid: i16 },
+}
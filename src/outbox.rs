@@ -0,0 +1,169 @@
+//! Transactional outbox: messages are written in the same transaction as the business
+//! data and later consumed by a poller that removes them once handled.
+//!
+//! # Example
+//! ```
+//! use mssql_client::{outbox::Outbox, Connection, Result};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let outbox = Outbox::new("dbo.Outbox".parse()?);
+//!     let connection = outbox.create_table(Connection::from_env("MSSQL_DB").await?).await?;
+//!
+//!     // producer
+//!     let tx = connection.transaction().await?;
+//!     let tx = outbox.enqueue(tx, "order.created", r#"{"id":1}"#).await?;
+//!     let connection = tx.commit().await?;
+//!
+//!     // consumer, the messages are deleted only if the transaction commits
+//!     let tx = connection.transaction().await?;
+//!     let (tx, messages) = outbox.poll(tx, 100).await?;
+//!
+//!     for message in &messages {
+//!         // publish the message...
+//!     }
+//!
+//!     tx.commit().await?;
+//!     Ok(())
+//! }
+//! ```
+use crate::{Connection, Result, SchemaIdent, Transaction};
+use chrono::NaiveDateTime;
+use futures03::future::LocalBoxFuture;
+
+/// An outbox table.
+#[derive(Clone, Debug)]
+pub struct Outbox {
+    table: SchemaIdent,
+}
+
+/// A message read from the outbox by [Outbox::poll](struct.Outbox.html#method.poll).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    pub id: i64,
+    pub topic: String,
+    pub payload: String,
+    /// The UTC time the message was enqueued.
+    pub created_at: NaiveDateTime,
+}
+
+impl Outbox {
+    /// Uses the `table` as outbox.
+    pub fn new(table: SchemaIdent) -> Self {
+        Self { table }
+    }
+
+    /// Creates the outbox table if it does not exist.
+    ///
+    /// The statement is sent without parameters, so that a `#temp` outbox is created in the
+    /// session rather than in the scope of `sp_executesql` and outlives the call.
+    pub fn create_table(
+        &self,
+        connection: Connection,
+    ) -> LocalBoxFuture<'static, Result<Connection>> {
+        let sql = format!(
+            r#"
+            IF OBJECT_ID(N'{}', 'U') IS NULL
+                CREATE TABLE {} (
+                    Id BIGINT IDENTITY NOT NULL PRIMARY KEY,
+                    Topic NVARCHAR(256) NOT NULL,
+                    Payload NVARCHAR(MAX) NOT NULL,
+                    CreatedAt DATETIME2 NOT NULL DEFAULT SYSUTCDATETIME()
+                )"#,
+            self.table.object_name().replace('\'', "''"),
+            self.table
+        );
+
+        connection.execute(sql, ())
+    }
+
+    /// Adds a message to the outbox inside the caller's transaction, so it is
+    /// published only if the transaction commits.
+    pub fn enqueue<'a>(
+        &self,
+        tx: Transaction,
+        topic: &'a str,
+        payload: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Transaction>> {
+        let sql = format!("INSERT INTO {} (Topic, Payload) VALUES (@p1, @p2)", self.table);
+        tx.execute(sql, (topic, payload))
+    }
+
+    /// Removes and returns up to `max` messages, oldest first.
+    ///
+    /// The rows are locked with `UPDLOCK` and rows locked by other consumers are skipped
+    /// with `READPAST`, so several consumers can poll concurrently without receiving the
+    /// same message. The messages come back if the transaction is rolled back.
+    pub fn poll(
+        &self,
+        tx: Transaction,
+        max: i32,
+    ) -> LocalBoxFuture<'static, Result<(Transaction, Vec<Message>)>> {
+        let sql = format!(
+            r#"
+            WITH q AS (
+                SELECT TOP (@p1) Id, Topic, Payload, CreatedAt
+                FROM {} WITH (ROWLOCK, READPAST, UPDLOCK)
+                ORDER BY Id
+            )
+            DELETE FROM q
+            OUTPUT deleted.Id, deleted.Topic, deleted.Payload, deleted.CreatedAt"#,
+            self.table
+        );
+
+        tx.query_map(sql, max, |row| {
+            Ok(Message {
+                id: row.get(0)?,
+                topic: row.get(1)?,
+                payload: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn enqueue_poll() -> Result<()> {
+        let outbox = Outbox::new("#Outbox".parse()?);
+        let connection = Connection::from_env("MSSQL_DB").await?;
+        let connection = outbox.create_table(connection).await?;
+
+        let tx = connection.transaction().await?;
+        let tx = outbox.enqueue(tx, "a", "1").await?;
+        let tx = outbox.enqueue(tx, "b", "2").await?;
+        let connection = tx.commit().await?;
+
+        let tx = connection.transaction().await?;
+        let (tx, messages) = outbox.poll(tx, 1).await?;
+        let connection = tx.rollback().await?;
+
+        assert_eq!(1, messages.len());
+        assert_eq!("a", messages[0].topic);
+
+        let tx = connection.transaction().await?;
+        let (tx, messages) = outbox.poll(tx, 10).await?;
+        tx.commit().await?;
+
+        let payloads = messages.iter().map(|m| m.payload.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["1", "2"], payloads);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_temp_table() -> Result<()> {
+        let outbox = Outbox::new("#Outbox".parse()?);
+        let connection = Connection::from_env("MSSQL_DB").await?;
+        let connection = outbox.create_table(connection).await?;
+        let connection = outbox.create_table(connection).await?;
+
+        let (_, count): (_, i32) =
+            connection.query_one("SELECT COUNT(*) FROM #Outbox", ()).await?;
+
+        assert_eq!(0, count);
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{uuid_ext::to_sql_guid, Error, Params};
 use chrono::{NaiveDate, NaiveDateTime};
 use std::borrow::Cow;
 use std::fmt::{Debug, Display, Error as FmtError, Formatter};
@@ -5,6 +6,9 @@ use tiberius::ty::{Guid, ToSql};
 use uuid::Uuid;
 
 pub enum Parameter<'a> {
+    /// A string declared as `VARCHAR`, see [Ansi](struct.Ansi.html).
+    AnsiString(Option<Cow<'a, str>>),
+    Binary(Option<Cow<'a, [u8]>>),
     Bool(Option<bool>),
     Date(Option<NaiveDate>),
     DateTime(Option<NaiveDateTime>),
@@ -14,6 +18,8 @@ pub enum Parameter<'a> {
     I32(Option<i32>),
     I64(Option<i64>),
     String(Option<Cow<'a, str>>),
+    /// A value declared with an explicit sql type, see [Parameter::typed](#method.typed).
+    Typed(SqlType, Box<Parameter<'a>>),
     Uuid(Option<Guid>),
 }
 
@@ -26,6 +32,9 @@ impl<'a> Debug for Parameter<'a> {
             }
         }
         match self {
+            Parameter::AnsiString(v) => write(f, v),
+            Parameter::Binary(Some(v)) => write!(f, "<{} bytes>", v.len()),
+            Parameter::Binary(None) => f.write_str("null"),
             Parameter::Bool(v) => write(f, v),
             Parameter::Date(v) => write(f, v),
             Parameter::DateTime(v) => write(f, v),
@@ -35,14 +44,122 @@ impl<'a> Debug for Parameter<'a> {
             Parameter::I32(v) => write(f, v),
             Parameter::I64(v) => write(f, v),
             Parameter::String(v) => write(f, v),
+            Parameter::Typed(_, p) => Debug::fmt(p, f),
             Parameter::Uuid(g) => write(f, g),
         }
     }
 }
 
+impl<'a> Parameter<'a> {
+    /// Declares the value with an explicit sql type instead of the type inferred from the
+    /// value, such as a `VARCHAR` to seek an index of a `VARCHAR` column instead of scanning it
+    /// because of the implicit conversion of an `NVARCHAR`.
+    ///
+    /// The statements with typed parameters are run through `sp_executesql`, which converts
+    /// the values to the declared types.
+    ///
+    /// # Panics
+    /// Panics if the value is not a single parameter, such as a tuple.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Parameter, Result, SqlType};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let (_, v): (_, String) = Connection::from_env("MSSQL_DB")
+    ///         .await?
+    ///         .query_scalar(
+    ///             "SELECT CAST(SQL_VARIANT_PROPERTY(@p1, 'BaseType') AS NVARCHAR(128))",
+    ///             Parameter::typed("abc", SqlType::VarChar(100)),
+    ///         )
+    ///         .await?;
+    ///
+    ///     assert_eq!("varchar", v);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn typed<P: Params<'a>>(value: P, ty: SqlType) -> Self {
+        let mut out = Vec::with_capacity(1);
+        value.params(&mut out);
+        assert_eq!(1, out.len(), "a typed parameter must be a single value");
+
+        Parameter::Typed(ty, Box::new(out.remove(0)))
+    }
+
+    /// The name of the variant, logged in place of the value when the parameters are redacted.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Parameter::AnsiString(_) => "AnsiString",
+            Parameter::Binary(_) => "Binary",
+            Parameter::Bool(_) => "Bool",
+            Parameter::Date(_) => "Date",
+            Parameter::DateTime(_) => "DateTime",
+            Parameter::F32(_) => "F32",
+            Parameter::F64(_) => "F64",
+            Parameter::I16(_) => "I16",
+            Parameter::I32(_) => "I32",
+            Parameter::I64(_) => "I64",
+            Parameter::String(_) => "String",
+            Parameter::Typed(_, p) => p.kind(),
+            Parameter::Uuid(_) => "Uuid",
+        }
+    }
+
+    /// The type declaring the parameter to `sp_executesql`, the variable length types are
+    /// declared with their maximum in-row length so that the plans are reused across values.
+    pub(crate) fn declared_type(&self) -> Cow<'static, str> {
+        Cow::Borrowed(match self {
+            Parameter::AnsiString(Some(v)) if v.chars().count() > 8000 => "VARCHAR(MAX)",
+            Parameter::AnsiString(_) => "VARCHAR(8000)",
+            Parameter::Binary(Some(v)) if v.len() > 8000 => "VARBINARY(MAX)",
+            Parameter::Binary(_) => "VARBINARY(8000)",
+            Parameter::Bool(_) => "BIT",
+            Parameter::Date(_) => "DATE",
+            Parameter::DateTime(_) => "DATETIME2",
+            Parameter::F32(_) => "REAL",
+            Parameter::F64(_) => "FLOAT",
+            Parameter::I16(_) => "SMALLINT",
+            Parameter::I32(_) => "INT",
+            Parameter::I64(_) => "BIGINT",
+            Parameter::String(_) if self.approx_size() > 8000 => "NVARCHAR(MAX)",
+            Parameter::String(_) => "NVARCHAR(4000)",
+            Parameter::Typed(ty, _) => return Cow::Owned(ty.to_string()),
+            Parameter::Uuid(_) => "UNIQUEIDENTIFIER",
+        })
+    }
+
+    /// The approximate number of bytes of the value sent to the server, 0 for null.
+    pub(crate) fn approx_size(&self) -> usize {
+        fn size<T>(v: &Option<T>, n: usize) -> usize {
+            v.as_ref().map_or(0, |_| n)
+        }
+
+        match self {
+            // sent as UTF-16 and converted by the server
+            Parameter::AnsiString(v) => v.as_ref().map_or(0, |s| s.encode_utf16().count() * 2),
+            Parameter::Binary(v) => v.as_ref().map_or(0, |b| b.len()),
+            Parameter::Bool(v) => size(v, 1),
+            Parameter::Date(v) => size(v, 3),
+            Parameter::DateTime(v) => size(v, 8),
+            Parameter::F32(v) => size(v, 4),
+            Parameter::F64(v) => size(v, 8),
+            Parameter::I16(v) => size(v, 2),
+            Parameter::I32(v) => size(v, 4),
+            Parameter::I64(v) => size(v, 8),
+            // sent as UTF-16
+            Parameter::String(v) => v.as_ref().map_or(0, |s| s.encode_utf16().count() * 2),
+            Parameter::Typed(_, p) => p.approx_size(),
+            Parameter::Uuid(v) => size(v, 16),
+        }
+    }
+}
+
 impl<'a> From<&'a Parameter<'a>> for &'a dyn ToSql {
     fn from(d: &'a Parameter<'a>) -> &'a dyn ToSql {
         match d {
+            Parameter::AnsiString(v) => v,
+            Parameter::Binary(v) => v,
             Parameter::Bool(v) => v,
             Parameter::Date(v) => v,
             Parameter::DateTime(v) => v,
@@ -52,6 +169,7 @@ impl<'a> From<&'a Parameter<'a>> for &'a dyn ToSql {
             Parameter::I32(v) => v,
             Parameter::I64(v) => v,
             Parameter::String(v) => v,
+            Parameter::Typed(_, p) => Self::from(&**p),
             Parameter::Uuid(v) => v,
         }
     }
@@ -59,12 +177,7 @@ impl<'a> From<&'a Parameter<'a>> for &'a dyn ToSql {
 
 impl<'a, 'b> From<&'b Uuid> for Parameter<'a> {
     fn from(id: &'b Uuid) -> Self {
-        let b = id.as_bytes();
-
-        Parameter::Uuid(Some(Guid::from_bytes(&[
-            b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9], b[10], b[11], b[12], b[13],
-            b[14], b[15],
-        ])))
+        Parameter::Uuid(Some(to_sql_guid(*id)))
     }
 }
 
@@ -73,3 +186,153 @@ impl<'a> From<Uuid> for Parameter<'a> {
         (&id).into()
     }
 }
+
+/// An sql type declaring a [typed parameter](enum.Parameter.html#method.typed).
+///
+/// The lengths are in characters for the string types and in bytes for the binary types.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SqlType {
+    BigInt,
+    Binary(u16),
+    Bit,
+    Char(u16),
+    Date,
+    DateTime,
+    DateTime2,
+    /// The precision and the scale.
+    Decimal(u8, u8),
+    Float,
+    Int,
+    NChar(u16),
+    NVarChar(u16),
+    NVarCharMax,
+    Real,
+    SmallInt,
+    Time,
+    TinyInt,
+    UniqueIdentifier,
+    VarBinary(u16),
+    VarBinaryMax,
+    VarChar(u16),
+    VarCharMax,
+}
+
+impl Display for SqlType {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            SqlType::BigInt => f.write_str("BIGINT"),
+            SqlType::Binary(n) => write!(f, "BINARY({})", n),
+            SqlType::Bit => f.write_str("BIT"),
+            SqlType::Char(n) => write!(f, "CHAR({})", n),
+            SqlType::Date => f.write_str("DATE"),
+            SqlType::DateTime => f.write_str("DATETIME"),
+            SqlType::DateTime2 => f.write_str("DATETIME2"),
+            SqlType::Decimal(p, s) => write!(f, "DECIMAL({}, {})", p, s),
+            SqlType::Float => f.write_str("FLOAT"),
+            SqlType::Int => f.write_str("INT"),
+            SqlType::NChar(n) => write!(f, "NCHAR({})", n),
+            SqlType::NVarChar(n) => write!(f, "NVARCHAR({})", n),
+            SqlType::NVarCharMax => f.write_str("NVARCHAR(MAX)"),
+            SqlType::Real => f.write_str("REAL"),
+            SqlType::SmallInt => f.write_str("SMALLINT"),
+            SqlType::Time => f.write_str("TIME"),
+            SqlType::TinyInt => f.write_str("TINYINT"),
+            SqlType::UniqueIdentifier => f.write_str("UNIQUEIDENTIFIER"),
+            SqlType::VarBinary(n) => write!(f, "VARBINARY({})", n),
+            SqlType::VarBinaryMax => f.write_str("VARBINARY(MAX)"),
+            SqlType::VarChar(n) => write!(f, "VARCHAR({})", n),
+            SqlType::VarCharMax => f.write_str("VARCHAR(MAX)"),
+        }
+    }
+}
+
+/// What to do with a string parameter longer than the configured maximum.
+///
+/// See [ConnectionFactory::max_param_len](struct.ConnectionFactory.html#method.max_param_len).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthPolicy {
+    /// Returns an [Error::ParameterTooLong](error/enum.Error.html#variant.ParameterTooLong).
+    Error,
+    /// Truncates the value and logs a `warn!` event.
+    Truncate,
+}
+
+/// Applies the length policy to the string parameters longer than `max` characters.
+pub(crate) fn check_length(
+    params: &mut [Parameter],
+    max: usize,
+    policy: LengthPolicy,
+) -> Result<(), Error> {
+    for (index, p) in params.iter_mut().enumerate() {
+        let p = match p {
+            Parameter::Typed(_, p) => &mut **p,
+            p => p,
+        };
+
+        if let Parameter::AnsiString(Some(s)) | Parameter::String(Some(s)) = p {
+            if let Some((end, _)) = s.char_indices().nth(max) {
+                let len = s.chars().count();
+
+                match policy {
+                    LengthPolicy::Error => return Err(Error::ParameterTooLong { index, len, max }),
+                    LengthPolicy::Truncate => {
+                        tracing::warn!(
+                            "parameter @P{} truncated from {} to {} characters",
+                            index + 1,
+                            len,
+                            max
+                        );
+
+                        if let Cow::Borrowed(b) = *s {
+                            *s = Cow::Borrowed(&b[..end]);
+                        } else {
+                            s.to_mut().truncate(end);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn approx_size_works() {
+    assert_eq!(3, Parameter::Binary(Some(vec![1, 2, 3].into())).approx_size());
+    assert_eq!(6, Parameter::String(Some("abc".into())).approx_size());
+    assert_eq!(4, Parameter::I32(Some(1)).approx_size());
+    assert_eq!(0, Parameter::I64(None).approx_size());
+}
+
+#[test]
+fn typed_works() {
+    let p = Parameter::typed("abc", SqlType::VarChar(10));
+
+    assert_eq!("VARCHAR(10)", p.declared_type());
+    assert_eq!("String", p.kind());
+    assert_eq!("abc", format!("{:?}", p));
+    assert_eq!("DECIMAL(18, 4)", SqlType::Decimal(18, 4).to_string());
+}
+
+#[test]
+fn ansi_string_works() {
+    let p = Parameter::AnsiString(Some("abc".into()));
+
+    assert_eq!("VARCHAR(8000)", p.declared_type());
+    assert_eq!("abc", format!("{:?}", p));
+}
+
+#[test]
+fn check_length_works() {
+    let mut params = vec![
+        Parameter::String(Some("abcdé".into())),
+        Parameter::String(Some("abc".to_owned().into())),
+        Parameter::I32(Some(12345)),
+    ];
+
+    assert!(check_length(&mut params, 3, LengthPolicy::Error).is_err());
+    assert!(check_length(&mut params, 3, LengthPolicy::Truncate).is_ok());
+    assert_eq!("abc", format!("{:?}", params[0]));
+    assert!(check_length(&mut params, 3, LengthPolicy::Error).is_ok());
+}
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, rc::Rc, sync::Arc};
 
 use crate::Parameter;
 use chrono::{NaiveDate, NaiveDateTime};
@@ -51,6 +51,62 @@ where
     fn params_null(_: &mut Vec<Parameter<'a>>) {}
 }
 
+impl<'a, T> Params<'a> for std::collections::BTreeSet<T>
+where
+    T: Params<'a>,
+{
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        self.into_iter().for_each(|v| v.params(out))
+    }
+
+    fn params_null(_: &mut Vec<Parameter<'a>>) {}
+}
+
+impl<'a, T> Params<'a> for std::collections::VecDeque<T>
+where
+    T: Params<'a>,
+{
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        self.into_iter().for_each(|v| v.params(out))
+    }
+
+    fn params_null(_: &mut Vec<Parameter<'a>>) {}
+}
+
+/// Binds the items of any iterator, in the order of the iteration.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Connection, ParamsIter, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let ids = (1..=3).map(|i| i * 10);
+///
+///     let (_, rows): (_, Vec<i32>) = Connection::from_env("MSSQL_DB")
+///         .await?
+///         .query("SELECT v FROM (VALUES (@p1), (@p2), (@p3)) t(v)", ParamsIter(ids))
+///         .await?;
+///
+///     assert_eq!(3, rows.len());
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParamsIter<I>(pub I);
+
+impl<'a, I> Params<'a> for ParamsIter<I>
+where
+    I: IntoIterator,
+    I::Item: Params<'a>,
+{
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        self.0.into_iter().for_each(|v| v.params(out))
+    }
+
+    fn params_null(_: &mut Vec<Parameter<'a>>) {}
+}
+
 impl<'a, T> Params<'a> for Vec<T>
 where
     T: Params<'a>,
@@ -62,6 +118,48 @@ where
     fn params_null(_: &mut Vec<Parameter<'a>>) {}
 }
 
+impl<'a, 'b, T> Params<'a> for &'b [T]
+where
+    &'b T: Params<'a>,
+{
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        self.iter().for_each(|v| v.params(out))
+    }
+
+    fn params_null(_: &mut Vec<Parameter<'a>>) {}
+}
+
+impl<'a, T, const N: usize> Params<'a> for [T; N]
+where
+    T: Params<'a>,
+{
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        IntoIterator::into_iter(self).for_each(|v| v.params(out))
+    }
+
+    fn params_null(_: &mut Vec<Parameter<'a>>) {}
+}
+
+impl<'a> Params<'a> for Vec<u8> {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::Binary(Some(Cow::Owned(self))))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::Binary(None))
+    }
+}
+
+impl<'a> Params<'a> for &'a [u8] {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::Binary(Some(Cow::Borrowed(self))))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::Binary(None))
+    }
+}
+
 impl<'a> Params<'a> for Parameter<'a> {
     fn params(self, out: &mut Vec<Parameter<'a>>) {
         out.push(self)
@@ -80,6 +178,16 @@ impl<'a> Params<'a> for bool {
     }
 }
 
+impl<'a> Params<'a> for char {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(Some(Cow::Owned(self.to_string()))))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(None))
+    }
+}
+
 impl<'a> Params<'a> for Decimal {
     fn params(self, out: &mut Vec<Parameter<'a>>) {
         out.push(Parameter::F64(Some(self.into())))
@@ -180,6 +288,35 @@ impl<'a> Params<'a> for &'a str {
     }
 }
 
+impl<'a> Params<'a> for Cow<'a, str> {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(Some(self)))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(None))
+    }
+}
+
+/// Implements `Params` for a reference to a shared string, bound without copying it.
+macro_rules! shared_str {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> Params<'a> for &'a $t {
+                fn params(self, out: &mut Vec<Parameter<'a>>) {
+                    out.push(Parameter::String(Some(Cow::Borrowed(&**self))))
+                }
+
+                fn params_null(out: &mut Vec<Parameter<'a>>) {
+                    out.push(Parameter::String(None))
+                }
+            }
+        )*
+    };
+}
+
+shared_str!(Arc<str>, Arc<String>, Rc<str>);
+
 impl<'a> Params<'a> for Uuid {
     fn params(self, out: &mut Vec<Parameter<'a>>) {
         out.push(self.into())
@@ -294,3 +431,59 @@ fn check_compile() {
         NaiveDate::from_ymd(2000, 1, 1).and_hms(12, 10, 1),
     ));
 }
+
+#[test]
+fn slices_and_arrays_works() {
+    let mut v = Vec::new();
+    let ids = vec![1, 2, 3];
+
+    ids.as_slice().params(&mut v);
+    ["a", "b"].params(&mut v);
+    (&ids[..1], [10i64; 2]).params(&mut v);
+
+    assert_eq!("[1, 2, 3, a, b, 1, 10, 10]", format!("{:?}", v));
+
+    // bound through the reference, without cloning the values
+    let names: Vec<Arc<str>> = vec!["x".into(), "y".into()];
+    let mut v = Vec::new();
+    names.as_slice().params(&mut v);
+
+    assert!(matches!(&v[0], Parameter::String(Some(Cow::Borrowed("x")))));
+    assert_eq!("[x, y]", format!("{:?}", v));
+}
+
+#[test]
+fn collections_works() {
+    use std::collections::{BTreeSet, VecDeque};
+
+    let mut v = Vec::new();
+    let set: BTreeSet<_> = vec![3, 1, 2].into_iter().collect();
+    let queue: VecDeque<_> = vec!["b", "a"].into_iter().collect();
+
+    set.params(&mut v);
+    queue.params(&mut v);
+    ParamsIter((1..3).map(i64::from)).params(&mut v);
+
+    assert_eq!("[1, 2, 3, b, a, 1, 2]", format!("{:?}", v));
+}
+
+#[test]
+fn shared_strings_works() {
+    let mut v = Vec::new();
+    let arc: Arc<str> = Arc::from("a");
+    let arc_string = Arc::new("b".to_owned());
+    let rc: Rc<str> = Rc::from("c");
+
+    (&arc, &arc_string, &rc, Cow::Borrowed("d")).params(&mut v);
+
+    assert_eq!("[a, b, c, d]", format!("{:?}", v));
+    assert!(matches!(&v[0], Parameter::String(Some(Cow::Borrowed(_)))));
+}
+
+#[test]
+fn char_works() {
+    let mut v = Vec::new();
+    ('A', None::<char>).params(&mut v);
+
+    assert_eq!("[A, null]", format!("{:?}", v));
+}
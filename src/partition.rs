@@ -0,0 +1,138 @@
+use crate::{Connection, ConnectionFactory, Error, FromRow, Result, SchemaIdent};
+use futures03::{
+    future::LocalBoxFuture,
+    stream::{self, StreamExt, TryStreamExt},
+};
+use tracing::instrument;
+
+/// A partition of a table, as listed by [Connection::partitions](struct.Connection.html#method.partitions).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Partition {
+    /// The 1-based partition number, usable with `$PARTITION` and `SWITCH PARTITION`.
+    pub number: i32,
+    /// The number of rows in the partition.
+    pub rows: i64,
+    /// The boundary value of the partition converted to text.
+    ///
+    /// This is the upper boundary for a `RANGE LEFT` function and the lower boundary
+    /// for a `RANGE RIGHT` function. It is `None` for the open-ended partition and
+    /// for tables that are not partitioned.
+    pub boundary: Option<String>,
+}
+
+impl Connection {
+    /// Lists the partitions of a table (heap or clustered index), ordered by partition number.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let orders = "dbo.Orders".parse()?;
+    ///     let (connection, partitions) = connection.partitions(&orders).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn partitions<'a>(
+        self,
+        table: &'a SchemaIdent,
+    ) -> LocalBoxFuture<'a, Result<(Self, Vec<Partition>)>> {
+        let sql = r#"
+            SELECT p.partition_number, p.rows, CAST(prv.value AS NVARCHAR(4000))
+            FROM sys.partitions p
+            INNER JOIN sys.indexes i ON i.object_id = p.object_id AND i.index_id = p.index_id
+            LEFT JOIN sys.partition_schemes ps ON ps.data_space_id = i.data_space_id
+            LEFT JOIN sys.partition_functions pf ON pf.function_id = ps.function_id
+            LEFT JOIN sys.partition_range_values prv ON prv.function_id = pf.function_id
+                AND prv.boundary_id = p.partition_number - pf.boundary_value_on_right
+            WHERE p.object_id = OBJECT_ID(@p1) AND p.index_id IN (0, 1)
+            ORDER BY p.partition_number"#;
+
+        self.query_map(sql, table.object_name(), |row| {
+            Ok(Partition {
+                number: row.get(0)?,
+                rows: row.get(1)?,
+                boundary: row.get(2)?,
+            })
+        })
+    }
+
+    /// Moves a partition of the `source` table into the `target` table using
+    /// `ALTER TABLE ... SWITCH PARTITION`.
+    ///
+    /// When `target_partition` is `None`, the target table must not be partitioned.
+    pub fn switch_partition<'a>(
+        self,
+        source: &'a SchemaIdent,
+        partition: i32,
+        target: &'a SchemaIdent,
+        target_partition: Option<i32>,
+    ) -> LocalBoxFuture<'a, Result<Self>> {
+        let mut sql = format!(
+            "ALTER TABLE {} SWITCH PARTITION {} TO {}",
+            source, partition, target
+        );
+
+        if let Some(p) = target_partition {
+            sql.push_str(&format!(" PARTITION {}", p));
+        }
+
+        self.execute(sql, ())
+    }
+}
+
+impl ConnectionFactory {
+    /// Runs a query once per partition of a table, using up to `parallelism` connections at a time.
+    ///
+    /// The partition number is bound to `@p1`, so the sql usually filters rows
+    /// with `$PARTITION.<function>(<column>) = @p1`. Results are returned in partition order.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{ConnectionFactory, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let factory = ConnectionFactory::from_env("MSSQL_DB")?;
+    ///
+    ///     let results = factory
+    ///         .query_per_partition::<i32>(
+    ///             &"dbo.Orders".parse()?,
+    ///             "SELECT COUNT(*) FROM dbo.Orders WHERE $PARTITION.pfOrderDate(OrderDate) = @p1",
+    ///             4,
+    ///         )
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(
+        level = "debug",
+        name = "ConnectionFactory::query_per_partition",
+        skip(self),
+        err
+    )]
+    pub async fn query_per_partition<T>(
+        &self,
+        table: &SchemaIdent,
+        sql: &str,
+        parallelism: usize,
+    ) -> Result<Vec<(Partition, Vec<T>)>>
+    where
+        T: FromRow,
+    {
+        let (_, partitions) = self.create_connection().await?.partitions(table).await?;
+
+        stream::iter(partitions)
+            .map(|p| async move {
+                let conn = self.create_connection().await?;
+                let (_, rows) = conn.query(sql.to_owned(), p.number).await?;
+                Result::<_, Error>::Ok((p, rows))
+            })
+            .buffered(parallelism.max(1))
+            .try_collect()
+            .await
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{Parameter, Params};
+
+/// Builds a sql statement and its parameters piece by piece, numbering the `@pN`
+/// placeholders of the bound values, for queries whose filters are only known at runtime.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Connection, QueryBuilder, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let name: Option<&str> = None;
+///     let mut qb = QueryBuilder::new("SELECT v FROM (VALUES (1), (2), (3)) t(v) WHERE 1 = 1");
+///
+///     qb.push(" AND v IN ").push_in(vec![1, 3]);
+///
+///     if let Some(name) = name {
+///         qb.push(" AND Name = ").push_bind(name);
+///     }
+///
+///     let (sql, params) = qb.build();
+///     let (_connection, rows): (_, Vec<i32>) = Connection::from_env("MSSQL_DB")
+///         .await?
+///         .query(sql, params)
+///         .await?;
+///
+///     assert_eq!(vec![1, 3], rows);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct QueryBuilder<'a> {
+    sql: String,
+    params: Vec<Parameter<'a>>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    pub fn new<S>(sql: S) -> Self
+    where
+        S: Into<String>,
+    {
+        QueryBuilder {
+            sql: sql.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Appends sql text as is.
+    pub fn push(&mut self, sql: &str) -> &mut Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    /// Appends a placeholder for each parameter of `value`, separated by commas.
+    pub fn push_bind<P>(&mut self, value: P) -> &mut Self
+    where
+        P: Params<'a>,
+    {
+        let offset = self.params.len();
+        value.params(&mut self.params);
+        self.push_placeholders(offset);
+        self
+    }
+
+    /// Appends a parenthesized list of placeholders, `(@p1, @p2)`, for use with `IN`.
+    ///
+    /// An empty list appends `(NULL)`, which matches no rows.
+    pub fn push_in<I, P>(&mut self, values: I) -> &mut Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Params<'a>,
+    {
+        let offset = self.params.len();
+        values.into_iter().for_each(|v| v.params(&mut self.params));
+
+        if self.params.len() == offset {
+            self.sql.push_str("(NULL)");
+        } else {
+            self.sql.push('(');
+            self.push_placeholders(offset);
+            self.sql.push(')');
+        }
+
+        self
+    }
+
+    /// The sql built so far.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// The parameters bound so far.
+    pub fn params(&self) -> &[Parameter<'a>] {
+        &self.params
+    }
+
+    /// Returns the sql and the parameters, to pass to `execute` or `query`.
+    pub fn build(self) -> (String, Vec<Parameter<'a>>) {
+        (self.sql, self.params)
+    }
+
+    fn push_placeholders(&mut self, offset: usize) {
+        for i in offset..self.params.len() {
+            if i > offset {
+                self.sql.push_str(", ");
+            }
+
+            self.sql.push_str(&format!("@p{}", i + 1));
+        }
+    }
+}
+
+#[test]
+fn query_builder_works() {
+    let mut qb = QueryBuilder::default();
+    qb.push("INSERT T (A, B) VALUES (")
+        .push_bind((1, 2))
+        .push("); DELETE T WHERE Id = ")
+        .push_bind(5)
+        .push(" OR Code IN ")
+        .push_in(vec!["a", "b"])
+        .push(" OR Kind IN ")
+        .push_in(Vec::<i32>::new());
+
+    let (sql, params) = qb.build();
+
+    assert_eq!(
+        "INSERT T (A, B) VALUES (@p1, @p2); DELETE T WHERE Id = @p3 OR Code IN (@p4, @p5) \
+        OR Kind IN (NULL)",
+        sql
+    );
+    assert_eq!(5, params.len());
+}
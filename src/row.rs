@@ -1,5 +1,5 @@
-use crate::{Error, FromColumn, Result, SqlValue};
-use tiberius::query::QueryRow;
+use crate::{sql_value::column_type, Error, FromColumn, Result, SqlValue};
+use tiberius::query::{QueryIdx, QueryRow};
 
 /// A row is a temporary struct that must be transformed into a
 /// definitive struct using the [FromColumn](trait.FromColumn.html) trait.
@@ -14,6 +14,17 @@ impl Row {
     {
         match <R::Value>::from_row(self, idx) {
             Ok(v) => R::from_column(v),
+            Err(Error::TiberiusField(e, idx)) => Err(match column_type(self, idx) {
+                Some(decoded_as) if !<R::Value>::check_db_ty(decoded_as) => {
+                    Error::ColumnTypeMismatch {
+                        index: idx,
+                        column_name: None,
+                        decoded_as,
+                        rust_type: std::any::type_name::<R>(),
+                    }
+                }
+                _ => Error::TiberiusField(e, idx),
+            }),
             Err(e) => Err(e),
         }
     }
@@ -25,7 +36,146 @@ impl Row {
     {
         match self.get(idx) {
             Ok(v) => Ok(v),
+            Err(Error::UnexpectedNull { index, .. }) => Err(Error::UnexpectedNull {
+                index,
+                column_name: Some(field_name),
+            }),
+            Err(Error::ColumnTypeMismatch {
+                index,
+                decoded_as,
+                rust_type,
+                ..
+            }) => Err(Error::ColumnTypeMismatch {
+                index,
+                column_name: Some(field_name),
+                decoded_as,
+                rust_type,
+            }),
             Err(e) => Err(Error::FieldName(Box::new(e), field_name)),
         }
     }
+
+    /// Selects a subset of the columns by name.
+    ///
+    /// An error is returned if a column is not found.
+    pub fn columns_matching<'a>(&'a self, names: &[&str]) -> Result<Projection<'a>> {
+        let indexes = names
+            .iter()
+            .map(|name| {
+                self.column_index(name)
+                    .ok_or_else(|| Error::String(format!("Column `{}` not found.", name)))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Projection { row: self, indexes })
+    }
+
+    /// Reads the columns of a [FromProjection](trait.FromProjection.html) type by name.
+    pub fn project<T>(&self) -> Result<T>
+    where
+        T: FromProjection,
+    {
+        T::from_projection(&self.columns_matching(T::COLUMNS)?)
+    }
+
+    /// Returns the index of the column named `name`, so that a
+    /// [FromRow](trait.FromRow.html) implementation does not depend on the column order.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{FromRow, Result, Row};
+    ///
+    /// struct Customer {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// impl FromRow for Customer {
+    ///     fn from_row(row: &Row) -> Result<Self> {
+    ///         let index = |name| row.column_index(name).ok_or("Column not found.");
+    ///
+    ///         Ok(Customer {
+    ///             id: row.get(index("Id")?)?,
+    ///             name: row.get(index("Name")?)?,
+    ///         })
+    ///     }
+    /// }
+    /// ```
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        name.to_idx(&self.0)
+    }
+
+    /// Returns `true` if the row has no columns.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of columns of the row.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A subset of the columns of a [Row](struct.Row.html), selected by name.
+pub struct Projection<'a> {
+    row: &'a Row,
+    indexes: Vec<usize>,
+}
+
+impl<'a> Projection<'a> {
+    /// Reads the column at the index `idx` of the selected columns.
+    pub fn get<R>(&self, idx: usize) -> Result<R>
+    where
+        R: FromColumn<'a>,
+    {
+        match self.indexes.get(idx) {
+            Some(&i) => self.row.get(i),
+            None => Err(Error::FieldNotFound(idx)),
+        }
+    }
+}
+
+/// Reads a group of columns by name, so that a group shared by many queries
+/// can be mapped once and reused by the [FromRow](trait.FromRow.html) implementations.
+///
+/// # Example
+/// ```
+/// use chrono::NaiveDateTime;
+/// use mssql_client::{FromProjection, FromRow, Projection, Result, Row};
+///
+/// struct Audit {
+///     created_by: String,
+///     created_at: NaiveDateTime,
+/// }
+///
+/// impl FromProjection for Audit {
+///     const COLUMNS: &'static [&'static str] = &["CreatedBy", "CreatedAt"];
+///
+///     fn from_projection(p: &Projection) -> Result<Self> {
+///         Ok(Audit {
+///             created_by: p.get(0)?,
+///             created_at: p.get(1)?,
+///         })
+///     }
+/// }
+///
+/// struct Order {
+///     id: i32,
+///     audit: Audit,
+/// }
+///
+/// impl FromRow for Order {
+///     fn from_row(row: &Row) -> Result<Self> {
+///         Ok(Order {
+///             id: row.get(0)?,
+///             audit: row.project()?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromProjection: Sized {
+    /// The names of the columns, in the order of the indexes used by `from_projection`.
+    const COLUMNS: &'static [&'static str];
+
+    fn from_projection(projection: &Projection) -> Result<Self>;
 }
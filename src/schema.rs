@@ -0,0 +1,244 @@
+//! Introspection of the tables, columns and indexes of the current database, read from
+//! the `sys` catalog views.
+//!
+//! # Example
+//! ```
+//! use mssql_client::{schema, Connection, Result};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let connection = Connection::from_env("MSSQL_DB").await?;
+//!     let (mut connection, tables) = schema::tables(connection).await?;
+//!
+//!     for table in &tables {
+//!         let name = table.ident()?;
+//!         let (c, columns) = schema::columns(connection, &name).await?;
+//!         connection = c;
+//!
+//!         println!("{} has {} columns", name, columns.len());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use crate::{Connection, Ident, Result, SchemaIdent};
+
+/// A user table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub object_id: i32,
+    pub schema: String,
+    pub name: String,
+}
+
+impl Table {
+    /// The schema-qualified name of the table.
+    pub fn ident(&self) -> Result<SchemaIdent> {
+        Ok(SchemaIdent::new(
+            Some(Ident::new(&self.schema)?),
+            Ident::new(&self.name)?,
+        ))
+    }
+}
+
+/// A column of a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    /// The system type name, such as `int` or `nvarchar`.
+    pub type_name: String,
+    /// The maximum length in bytes, `-1` for `max` types.
+    pub max_length: i16,
+    pub precision: i8,
+    pub scale: i8,
+    pub is_nullable: bool,
+    pub is_identity: bool,
+    pub is_computed: bool,
+    /// The definition of the default constraint, such as `((0))`.
+    pub default: Option<String>,
+}
+
+/// The primary key of a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrimaryKey {
+    pub name: String,
+    /// The key columns, in key order.
+    pub columns: Vec<String>,
+}
+
+/// An index of a table, including the primary key and unique constraints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Index {
+    pub name: String,
+    /// The index type, such as `CLUSTERED` or `NONCLUSTERED`.
+    pub kind: String,
+    pub is_unique: bool,
+    pub is_primary_key: bool,
+    /// The key columns in key order, followed by the included columns.
+    pub columns: Vec<IndexColumn>,
+}
+
+/// A column of an [Index](struct.Index.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexColumn {
+    pub name: String,
+    pub is_descending: bool,
+    pub is_included: bool,
+}
+
+/// Lists the user tables, ordered by schema and name.
+pub async fn tables(conn: Connection) -> Result<(Connection, Vec<Table>)> {
+    let sql = r#"
+        SELECT t.object_id, s.name, t.name
+        FROM sys.tables t
+        INNER JOIN sys.schemas s ON s.schema_id = t.schema_id
+        WHERE t.is_ms_shipped = 0
+        ORDER BY s.name, t.name"#;
+
+    conn.query_map(sql, (), |row| {
+        Ok(Table {
+            object_id: row.get(0)?,
+            schema: row.get(1)?,
+            name: row.get(2)?,
+        })
+    })
+    .await
+}
+
+/// Lists the columns of a table, in column order.
+///
+/// The list is empty if the table does not exist.
+pub async fn columns(conn: Connection, table: &SchemaIdent) -> Result<(Connection, Vec<Column>)> {
+    let sql = r#"
+        SELECT
+            c.name, TYPE_NAME(c.system_type_id), c.max_length, c.precision, c.scale,
+            c.is_nullable, c.is_identity, c.is_computed, d.definition
+        FROM sys.columns c
+        LEFT JOIN sys.default_constraints d ON d.object_id = c.default_object_id
+        WHERE c.object_id = OBJECT_ID(@p1)
+        ORDER BY c.column_id"#;
+
+    conn.query_map(sql, table.object_name(), |row| {
+        Ok(Column {
+            name: row.get(0)?,
+            type_name: row.get(1)?,
+            max_length: row.get(2)?,
+            precision: row.get(3)?,
+            scale: row.get(4)?,
+            is_nullable: row.get(5)?,
+            is_identity: row.get(6)?,
+            is_computed: row.get(7)?,
+            default: row.get(8)?,
+        })
+    })
+    .await
+}
+
+/// Returns the primary key of a table, `None` if it has none or does not exist.
+pub async fn primary_key(
+    conn: Connection,
+    table: &SchemaIdent,
+) -> Result<(Connection, Option<PrimaryKey>)> {
+    let (conn, indexes) = indexes(conn, table).await?;
+
+    let pk = indexes.into_iter().find(|i| i.is_primary_key).map(|i| PrimaryKey {
+        name: i.name,
+        columns: i.columns.into_iter().map(|c| c.name).collect(),
+    });
+
+    Ok((conn, pk))
+}
+
+/// Lists the indexes of a table, ordered by index id; heaps are not listed.
+pub async fn indexes(conn: Connection, table: &SchemaIdent) -> Result<(Connection, Vec<Index>)> {
+    let sql = r#"
+        SELECT
+            i.index_id, i.name, i.type_desc, i.is_unique, i.is_primary_key,
+            c.name, ic.is_descending_key, ic.is_included_column
+        FROM sys.indexes i
+        INNER JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id
+        INNER JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id
+        WHERE i.object_id = OBJECT_ID(@p1) AND i.type > 0
+        ORDER BY i.index_id, ic.is_included_column, ic.key_ordinal, ic.index_column_id"#;
+
+    let (conn, rows) = conn
+        .query_map(sql, table.object_name(), |row| {
+            let id: i32 = row.get(0)?;
+
+            let index = Index {
+                name: row.get(1)?,
+                kind: row.get(2)?,
+                is_unique: row.get(3)?,
+                is_primary_key: row.get(4)?,
+                columns: Vec::new(),
+            };
+
+            let column = IndexColumn {
+                name: row.get(5)?,
+                is_descending: row.get(6)?,
+                is_included: row.get(7)?,
+            };
+
+            Ok((id, index, column))
+        })
+        .await?;
+
+    let mut indexes: Vec<(i32, Index)> = Vec::new();
+
+    for (id, mut index, column) in rows {
+        if let Some((last, current)) = indexes.last_mut() {
+            if *last == id {
+                current.columns.push(column);
+                continue;
+            }
+        }
+
+        index.columns.push(column);
+        indexes.push((id, index));
+    }
+
+    Ok((conn, indexes.into_iter().map(|(_, i)| i).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn schema() -> Result<()> {
+        let table = "#Schema".parse()?;
+        // the catalog views only list the temporary tables from tempdb
+        let conn = Connection::from_env("MSSQL_DB")
+            .await?
+            .use_database(&"tempdb".parse()?)
+            .await?
+            .execute(
+                r#"
+                CREATE TABLE #Schema (
+                    Id INT IDENTITY NOT NULL,
+                    Code NVARCHAR(10) NOT NULL DEFAULT (''),
+                    Name NVARCHAR(MAX) NULL,
+                    PRIMARY KEY (Id)
+                );
+                CREATE UNIQUE INDEX IX_Code ON #Schema (Code DESC) INCLUDE (Name)"#,
+                (),
+            )
+            .await?;
+
+        let (conn, columns) = columns(conn, &table).await?;
+        assert_eq!(3, columns.len());
+        assert!(columns[0].is_identity);
+        assert_eq!("nvarchar", columns[1].type_name);
+        assert!(columns[1].default.is_some());
+        assert_eq!(-1, columns[2].max_length);
+
+        let (conn, pk) = primary_key(conn, &table).await?;
+        assert_eq!(vec!["Id"], pk.unwrap().columns);
+
+        let (_, indexes) = indexes(conn, &table).await?;
+        let ix = &indexes[1];
+        assert!(ix.is_unique);
+        assert!(ix.columns[0].is_descending);
+        assert!(ix.columns[1].is_included);
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{
+    utils::quote_ident, Connection, Error, Parameter, Params, Result, SchemaIdent, Transaction,
+};
+use futures03::future::LocalBoxFuture;
+use tracing::instrument;
+
+/// Loads fixture rows into tables, parents before children, inside one transaction.
+///
+/// The insertion order is resolved from the foreign keys of the database, so the tables
+/// can be given in any order. Identity values can be provided explicitly, in which case
+/// `IDENTITY_INSERT` is turned on for the table while its rows are inserted.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Connection, Result, Seeder};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let connection = Connection::from_env("MSSQL_DB").await?;
+///
+///     let connection = Seeder::new()
+///         .rows("dbo.Orders".parse()?, &["Id", "CustomerId"], vec![(10, 1), (11, 1)])
+///         .rows("dbo.Customers".parse()?, &["Id", "Name"], vec![(1, "Foo")])
+///         .seed(connection)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Seeder {
+    tables: Vec<SeedTable>,
+}
+
+#[derive(Debug)]
+struct SeedTable {
+    columns: Vec<String>,
+    name: SchemaIdent,
+    rows: Vec<Vec<Parameter<'static>>>,
+}
+
+impl Seeder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds rows to insert into `table`, each row giving one value per column.
+    pub fn rows<P, I>(mut self, table: SchemaIdent, columns: &[&str], rows: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Params<'static>,
+    {
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let mut p = Vec::new();
+                row.params(&mut p);
+                p
+            })
+            .collect();
+
+        self.tables.push(SeedTable {
+            columns: columns.iter().map(|c| (*c).to_owned()).collect(),
+            name: table,
+            rows,
+        });
+
+        self
+    }
+
+    /// Inserts the rows and commits. Nothing is inserted if an error occurs.
+    pub fn seed(self, connection: Connection) -> LocalBoxFuture<'static, Result<Connection>> {
+        Box::pin(self.seed_imp(connection))
+    }
+
+    #[instrument(level = "debug", name = "Seeder::seed", skip(self, connection), err)]
+    async fn seed_imp(self, connection: Connection) -> Result<Connection> {
+        let mut tx = connection.transaction().await?;
+        let mut ids = Vec::with_capacity(self.tables.len());
+
+        for table in &self.tables {
+            let (t, id): (_, Vec<Option<i32>>) = tx
+                .query("SELECT OBJECT_ID(@p1)", table.name.object_name())
+                .await?;
+
+            tx = t;
+
+            match id.into_iter().next().flatten() {
+                Some(id) => ids.push(id),
+                None => return Err(Error::String(format!("Table `{}` not found.", table.name))),
+            }
+        }
+
+        let (t, fks): (_, Vec<(i32, i32)>) = tx
+            .query("SELECT parent_object_id, referenced_object_id FROM sys.foreign_keys", ())
+            .await?;
+
+        tx = t;
+
+        let mut tables = self.tables.into_iter().map(Some).collect::<Vec<_>>();
+
+        for index in insertion_order(&ids, &fks)? {
+            let table = tables[index].take().expect("table");
+            tx = insert_table(tx, ids[index], table).await?;
+        }
+
+        tx.commit().await
+    }
+}
+
+async fn insert_table(tx: Transaction, id: i32, table: SeedTable) -> Result<Transaction> {
+    let (mut tx, identity): (_, Vec<String>) = tx
+        .query("SELECT name FROM sys.identity_columns WHERE object_id = @p1", id)
+        .await?;
+
+    let name = table.name.to_string();
+    let columns = table
+        .columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let identity_insert = identity
+        .iter()
+        .any(|i| table.columns.iter().any(|c| c.eq_ignore_ascii_case(i)));
+
+    if identity_insert {
+        tx = tx
+            .execute(format!("SET IDENTITY_INSERT {} ON", name), ())
+            .await?;
+    }
+
+    for row in table.rows {
+        if row.len() != table.columns.len() {
+            return Err(Error::String(format!(
+                "Seed row for `{}` has {} values for {} columns.",
+                table.name,
+                row.len(),
+                table.columns.len()
+            )));
+        }
+
+        let values = (1..=row.len())
+            .map(|i| format!("@p{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let sql = format!("INSERT INTO {} ({}) VALUES ({})", name, columns, values);
+        tx = tx.execute(sql, row).await?;
+    }
+
+    if identity_insert {
+        tx = tx
+            .execute(format!("SET IDENTITY_INSERT {} OFF", name), ())
+            .await?;
+    }
+
+    Ok(tx)
+}
+
+/// Orders the tables so that referenced tables come first, keeping the given
+/// order otherwise. Self references are ignored.
+fn insertion_order(ids: &[i32], fks: &[(i32, i32)]) -> Result<Vec<usize>> {
+    let mut order = Vec::with_capacity(ids.len());
+    let mut placed = vec![false; ids.len()];
+
+    while order.len() < ids.len() {
+        // a table is ready once all the seeded tables it references are placed
+        let ready = |i: usize| {
+            fks.iter()
+                .filter(|&&(child, parent)| child == ids[i] && parent != child)
+                .all(|&(_, parent)| (0..ids.len()).all(|j| placed[j] || ids[j] != parent))
+        };
+
+        let next = (0..ids.len()).find(|&i| !placed[i] && ready(i));
+
+        match next {
+            Some(i) => {
+                placed[i] = true;
+                order.push(i);
+            }
+            None => return Err(Error::Str("Circular foreign keys between seeded tables.")),
+        }
+    }
+
+    Ok(order)
+}
+
+#[test]
+fn insertion_order_works() {
+    // 1 references 2, 2 references 3, 4 references itself
+    let fks = [(1, 2), (2, 3), (4, 4), (5, 6)];
+
+    assert_eq!(vec![2, 1, 0, 3], insertion_order(&[1, 2, 3, 4], &fks).unwrap());
+    assert_eq!(vec![0, 1], insertion_order(&[3, 4], &fks).unwrap());
+    assert!(insertion_order(&[1, 2], &[(1, 2), (2, 1)]).is_err());
+}
@@ -0,0 +1,177 @@
+use crate::{command::query_first, Command, Error, FromRow, Params, Result};
+use std::{borrow::Cow, fmt::Debug};
+
+/// Holds a [Command](trait.Command.html), such as a [Connection](struct.Connection.html) or
+/// a [Transaction](struct.Transaction.html), and runs the statements through `&mut self`,
+/// so that it can live in a struct field and be used in loops and branches.
+///
+/// The methods of `Command` consume the command and drop it on error; likewise, after an
+/// error the session is left without its command and the next calls fail. The session
+/// keeps its command when [fetch_one](#method.fetch_one) or [fetch_opt](#method.fetch_opt)
+/// fail only because of the number of rows.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Connection, Result, Session};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut session = Session::new(Connection::from_env("MSSQL_DB").await?);
+///     session.exec("CREATE TABLE #T (V INT)", ()).await?;
+///
+///     for v in 0..3 {
+///         if v != 1 {
+///             session.exec("INSERT #T VALUES (@p1)", v).await?;
+///         }
+///     }
+///
+///     let rows: Vec<i32> = session.fetch("SELECT V FROM #T ORDER BY V", ()).await?;
+///     assert_eq!(vec![0, 2], rows);
+///     Ok(())
+/// }
+/// ```
+pub struct Session<C>(Option<C>);
+
+impl<C> Session<C>
+where
+    C: Command,
+{
+    pub fn new(command: C) -> Self {
+        Session(Some(command))
+    }
+
+    /// Returns `true` if the command was dropped after an error.
+    pub fn is_broken(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns the command, to commit a transaction for example.
+    pub fn into_inner(self) -> Result<C> {
+        self.0.ok_or(Error::SessionBroken)
+    }
+
+    /// Executes an sql command that does not returns rows.
+    pub async fn exec<'a, S, P>(&mut self, sql: S, params: P) -> Result<()>
+    where
+        C: 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+    {
+        let c = self.take()?.execute(sql, params).await?;
+        self.0 = Some(c);
+        Ok(())
+    }
+
+    /// Queries the database and reads all rows.
+    pub async fn fetch<'a, T, S, P>(&mut self, sql: S, params: P) -> Result<Vec<T>>
+    where
+        C: 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        let (c, rows) = self.take()?.query(sql, params).await?;
+        self.0 = Some(c);
+        Ok(rows)
+    }
+
+    /// Queries the database and reads the only row.
+    ///
+    /// See [Command::query_one](trait.Command.html#method.query_one), the session is not
+    /// broken by `NoRows` or `TooManyRows`.
+    pub async fn fetch_one<'a, T, S, P>(&mut self, sql: S, params: P) -> Result<T>
+    where
+        C: 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        match self.fetch_first(sql, params).await? {
+            (Some(row), 1) => Ok(row),
+            (_, 0) => Err(Error::NoRows),
+            (_, count) => Err(Error::TooManyRows(count)),
+        }
+    }
+
+    /// Queries the database and reads the row if there is one.
+    ///
+    /// See [Command::query_opt](trait.Command.html#method.query_opt), the session is not
+    /// broken by `TooManyRows`.
+    pub async fn fetch_opt<'a, T, S, P>(&mut self, sql: S, params: P) -> Result<Option<T>>
+    where
+        C: 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        match self.fetch_first(sql, params).await? {
+            (_, count) if count > 1 => Err(Error::TooManyRows(count)),
+            (row, _) => Ok(row),
+        }
+    }
+
+    /// Reads the first row and the number of rows, keeping the command.
+    async fn fetch_first<'a, T, S, P>(&mut self, sql: S, params: P) -> Result<(Option<T>, u64)>
+    where
+        C: 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        let (c, row, count) = query_first(self.take()?, sql, params).await?;
+        self.0 = Some(c);
+        Ok((row, count))
+    }
+
+    fn take(&mut self) -> Result<C> {
+        self.0.take().ok_or(Error::SessionBroken)
+    }
+}
+
+impl<C> From<C> for Session<C>
+where
+    C: Command,
+{
+    fn from(command: C) -> Self {
+        Session::new(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+
+    #[tokio::test]
+    async fn session() -> Result<()> {
+        let transaction = Connection::from_env("MSSQL_DB").await?.transaction().await?;
+        let mut session = Session::new(transaction);
+
+        session.exec("CREATE TABLE #T (V INT)", ()).await?;
+        session.exec("INSERT #T VALUES (@p1), (@p2)", (1, 2)).await?;
+
+        let v: Option<i32> = session.fetch_opt("SELECT MAX(V) FROM #T", ()).await?;
+        assert_eq!(Some(2), v);
+
+        // the number of rows does not break the session
+        let r = session.fetch_one::<i32, _, _>("SELECT V FROM #T WHERE V > 2", ()).await;
+        assert!(matches!(r, Err(Error::NoRows)));
+
+        let r = session.fetch_opt::<i32, _, _>("SELECT V FROM #T", ()).await;
+        assert!(matches!(r, Err(Error::TooManyRows(2))));
+        assert!(!session.is_broken());
+
+        let v: i32 = session.fetch_one("SELECT V FROM #T WHERE V = 1", ()).await?;
+        assert_eq!(1, v);
+
+        session.into_inner()?.commit().await?;
+
+        let mut session = Session::new(Connection::from_env("MSSQL_DB").await?);
+        assert!(session.exec("SELECT * FROM #Missing", ()).await.is_err());
+        assert!(session.is_broken());
+
+        let r = session.fetch::<i32, _, _>("SELECT 1", ()).await;
+        assert!(matches!(r, Err(Error::SessionBroken)));
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{Command, Connection, ConnectionFactory, FromRow, Params, Result, Row};
+use futures03::{channel::oneshot, future::LocalBoxFuture};
+use std::{borrow::Cow, cell::RefCell, collections::VecDeque, fmt::Debug, rc::Rc};
+
+/// A connection shared by many tasks running on the same thread.
+///
+/// Operations are queued in FIFO order and run one at a time over a single connection.
+/// The connection is created from the factory on first use and created again after an error.
+/// This is meant for many small point reads, where opening a connection per task costs
+/// more than waiting for the previous query.
+///
+/// SQL Server MARS is not supported by the underlying driver, so this queue is the way to
+/// share a connection between logically concurrent operations. It also implements
+/// [Command](trait.Command.html), and a clone can be handed to any code expecting one.
+///
+/// This is experimental.
+///
+/// # Example
+/// ```
+/// use mssql_client::{ConnectionFactory, Result, SharedConnection};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let shared = SharedConnection::new(ConnectionFactory::from_env("MSSQL_DB")?);
+///
+///     let (a, b) = futures03::future::join(
+///         shared.query::<i32, _, _>("SELECT @p1", 1),
+///         shared.query::<i32, _, _>("SELECT @p1", 2),
+///     )
+///     .await;
+///
+///     assert_eq!(1, a?[0]);
+///     assert_eq!(2, b?[0]);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedConnection(Rc<Inner>);
+
+struct Inner {
+    factory: ConnectionFactory,
+    state: RefCell<State>,
+}
+
+#[derive(Default)]
+struct State {
+    busy: bool,
+    conn: Option<Connection>,
+    waiters: VecDeque<oneshot::Sender<()>>,
+}
+
+impl Inner {
+    /// Hand the connection over to the next waiter or mark it as free.
+    fn release(&self, conn: Option<Connection>) {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(conn) = conn {
+            state.conn = Some(conn);
+        }
+
+        while let Some(waiter) = state.waiters.pop_front() {
+            if waiter.send(()).is_ok() {
+                return;
+            }
+        }
+
+        state.busy = false;
+    }
+}
+
+/// Exclusive access to the shared connection, released on drop.
+struct Lease<'a> {
+    inner: &'a Inner,
+    conn: Option<Connection>,
+}
+
+impl<'a> Lease<'a> {
+    async fn connection(&mut self) -> Result<Connection> {
+        match self.conn.take() {
+            Some(conn) => Ok(conn),
+            None => self.inner.factory.create_connection().await,
+        }
+    }
+}
+
+impl<'a> Drop for Lease<'a> {
+    fn drop(&mut self) {
+        self.inner.release(self.conn.take());
+    }
+}
+
+/// A queued request for the lease, which passes the lease on if dropped after being granted.
+struct Waiter<'a> {
+    inner: &'a Inner,
+    rx: Option<oneshot::Receiver<()>>,
+}
+
+impl<'a> Drop for Waiter<'a> {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            rx.close();
+
+            if let Ok(Some(())) = rx.try_recv() {
+                self.inner.release(None);
+            }
+        }
+    }
+}
+
+impl Command for SharedConnection {
+    fn execute<'a, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<Self>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+    {
+        Box::pin(async move {
+            SharedConnection::execute(&self, sql, params).await?;
+            Ok(self)
+        })
+    }
+
+    fn query_fold<'a, T, S, P, F>(
+        self,
+        sql: S,
+        params: P,
+        init: T,
+        func: F,
+    ) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        F: FnMut(T, &Row) -> Result<T> + 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: 'a,
+        Self: Sized,
+    {
+        Box::pin(async move {
+            let value = SharedConnection::query_fold(&self, sql, params, init, func).await?;
+            Ok((self, value))
+        })
+    }
+}
+
+impl SharedConnection {
+    /// Creates a shared connection that connects using the factory.
+    pub fn new(factory: ConnectionFactory) -> Self {
+        SharedConnection(Rc::new(Inner {
+            factory,
+            state: RefCell::new(State::default()),
+        }))
+    }
+
+    async fn lease(&self) -> Lease<'_> {
+        let inner = &*self.0;
+
+        let waiter = {
+            let mut state = inner.state.borrow_mut();
+
+            if state.busy {
+                let (tx, rx) = oneshot::channel();
+                state.waiters.push_back(tx);
+                Some(Waiter {
+                    inner,
+                    rx: Some(rx),
+                })
+            } else {
+                state.busy = true;
+                None
+            }
+        };
+
+        if let Some(mut waiter) = waiter {
+            if let Some(rx) = waiter.rx.as_mut() {
+                // senders are only dropped once the receiver is gone.
+                let _ = rx.await;
+            }
+
+            waiter.rx = None;
+        }
+
+        let conn = inner.state.borrow_mut().conn.take();
+        Lease { inner, conn }
+    }
+
+    /// Execute sql statements that don't return rows.
+    pub async fn execute<'a, S, P>(&self, sql: S, params: P) -> Result<()>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+    {
+        let mut lease = self.lease().await;
+        let conn = lease.connection().await?.execute(sql, params).await?;
+
+        lease.conn = Some(conn);
+        Ok(())
+    }
+
+    /// Execute sql query and returns all the rows.
+    pub async fn query<'a, T, S, P>(&self, sql: S, params: P) -> Result<Vec<T>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        let mut lease = self.lease().await;
+        let (conn, rows) = lease.connection().await?.query(sql, params).await?;
+
+        lease.conn = Some(conn);
+        Ok(rows)
+    }
+
+    /// Execute sql query and reduce the rows using a function.
+    pub async fn query_fold<'a, T, S, P, F>(
+        &self,
+        sql: S,
+        params: P,
+        init: T,
+        func: F,
+    ) -> Result<T>
+    where
+        F: FnMut(T, &Row) -> Result<T> + 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: 'a,
+    {
+        let mut lease = self.lease().await;
+        let (conn, value) = lease
+            .connection()
+            .await?
+            .query_fold(sql, params, init, func)
+            .await?;
+
+        lease.conn = Some(conn);
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures03::future::try_join_all;
+
+    #[tokio::test]
+    async fn command() -> Result<()> {
+        let shared = SharedConnection::new(ConnectionFactory::from_env("MSSQL_DB")?);
+        let (a, b) = futures03::future::join(
+            Command::query::<i32, _, _>(shared.clone(), "SELECT 1", ()),
+            Command::query::<i32, _, _>(shared, "SELECT 2", ()),
+        )
+        .await;
+
+        assert_eq!(1, a?.1[0]);
+        assert_eq!(2, b?.1[0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_concurrently() -> Result<()> {
+        let shared = SharedConnection::new(ConnectionFactory::from_env("MSSQL_DB")?);
+
+        let rows = try_join_all((0..10).map(|i| shared.query::<i32, _, _>("SELECT @p1", i))).await?;
+
+        for (i, rows) in rows.iter().enumerate() {
+            assert_eq!(i as i32, rows[0]);
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{row::Row, Error, Result};
+use crate::{row::Row, uuid_ext::from_sql_guid, Error, Money, Result};
 use chrono::{NaiveDate, NaiveDateTime};
 use decimal::Decimal;
 use tiberius::ty::{Guid, Numeric};
@@ -25,7 +25,7 @@ macro_rules! sql_value {
             }
 
             fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
-                read(row.0.try_get(idx), idx).map($e)
+                read(row, row.0.try_get(idx), idx).map($e)
             }
         }
 
@@ -39,12 +39,98 @@ macro_rules! sql_value {
             }
 
             fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
-                read(row.0.try_get(idx), idx).map(|v: Option<_>| v.map($e))
+                read(row, row.0.try_get(idx), idx).map(|v: Option<_>| v.map($e))
             }
         }
     };
 }
 
+/// Like `sql_value!` for an integer that can also be read from narrower integer columns.
+macro_rules! sql_int {
+    ($t:ty, [$($narrow:ty),*], $v:ident => $vv:expr) => {
+        impl<'a> SqlValue<'a> for $t {
+            fn check_db_ty($v: &str) -> bool {
+                $vv $(|| <$narrow>::check_db_ty($v))*
+            }
+
+            fn is_nullable() -> bool {
+                false
+            }
+
+            fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
+                let exact = row.0.try_get(idx);
+
+                if let Ok(Some(v)) = exact {
+                    return Ok(v);
+                }
+
+                $(
+                    let narrow: std::result::Result<Option<$narrow>, tiberius::Error> =
+                        row.0.try_get(idx);
+
+                    if let Ok(Some(v)) = narrow {
+                        return Ok(v.widen());
+                    }
+                )*
+
+                read(row, exact, idx)
+            }
+        }
+
+        impl<'a> SqlValue<'a> for Option<$t> {
+            fn check_db_ty(v: &str) -> bool {
+                <$t>::check_db_ty(v)
+            }
+
+            fn is_nullable() -> bool {
+                true
+            }
+
+            fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
+                let exact = row.0.try_get(idx);
+
+                if let Ok(Some(v)) = exact {
+                    return Ok(v);
+                }
+
+                $(
+                    let narrow: std::result::Result<Option<Option<$narrow>>, tiberius::Error> =
+                        row.0.try_get(idx);
+
+                    if let Ok(Some(v)) = narrow {
+                        return Ok(v.map(Widen::widen));
+                    }
+                )*
+
+                read(row, exact, idx)
+            }
+        }
+    };
+}
+
+/// Converts a narrower integer column, `tinyint` being unsigned but decoded as a `i8`.
+trait Widen<T> {
+    fn widen(self) -> T;
+}
+
+impl<T: From<u8>> Widen<T> for i8 {
+    fn widen(self) -> T {
+        (self as u8).into()
+    }
+}
+
+impl<T: From<i16>> Widen<T> for i16 {
+    fn widen(self) -> T {
+        self.into()
+    }
+}
+
+impl<T: From<i32>> Widen<T> for i32 {
+    fn widen(self) -> T {
+        self.into()
+    }
+}
+
 #[rustfmt::skip]
 mod m {
     use super::*;
@@ -55,23 +141,62 @@ mod m {
     sql_value!(NaiveDate, identity, v => v == "date");
     sql_value!(NaiveDateTime, identity, v => v == "datetime" || v == "datetime2" || v == "datetimeoffset");
     sql_value!(String, |v: &str| v.to_string(), v => <&str>::check_db_ty(v));
-    sql_value!(Uuid, guid_to_uuid, v => v == "uniqueidentifier");
+    sql_value!(Uuid, from_sql_guid, v => v == "uniqueidentifier");
     sql_value!(Vec<u8>, |v: &[u8]| v.to_vec(), v => <&[u8]>::check_db_ty(v));
     sql_value!(bool, identity, v => v == "bit");
     sql_value!(f32, identity, v => v == "real" || v == "smallmoney");
     sql_value!(f64, identity, v => v == "float" || v == "money");
-    sql_value!(i16, identity, v => v == "smallint");
-    sql_value!(i32, identity, v => v == "int");
-    sql_value!(i64, identity, v => v == "bigint");
+    sql_int!(i16, [i8], v => v == "smallint");
+    sql_int!(i32, [i16, i8], v => v == "int");
+    sql_int!(i64, [i32, i16, i8], v => v == "bigint");
     sql_value!(i8, identity, v => v == "tinyint");
+    sql_value!(u8, |v: i8| v as u8, v => v == "tinyint");
+}
+
+impl<'a> SqlValue<'a> for Money {
+    fn check_db_ty(v: &str) -> bool {
+        v == "money" || v == "smallmoney"
+    }
+
+    fn is_nullable() -> bool {
+        false
+    }
+
+    /// `money` is decoded as a `f64` and `smallmoney` as a `f32`.
+    fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
+        match row.0.try_get(idx) {
+            Ok(Some(v)) => Ok(Money::from_f64(v)),
+            _ => read(row, row.0.try_get(idx), idx).map(|v: f32| Money::from_f64(v.into())),
+        }
+    }
+}
+
+impl<'a> SqlValue<'a> for Option<Money> {
+    fn check_db_ty(v: &str) -> bool {
+        Money::check_db_ty(v)
+    }
+
+    fn is_nullable() -> bool {
+        true
+    }
+
+    fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
+        match row.0.try_get(idx) {
+            Ok(Some(v)) => Ok(v.map(Money::from_f64)),
+            _ => read(row, row.0.try_get(idx), idx)
+                .map(|v: Option<f32>| v.map(|v| Money::from_f64(v.into()))),
+        }
+    }
 }
 
 mod private {
+    use crate::Money;
     use decimal::Decimal;
     use uuid::Uuid;
 
     pub trait Sealed {}
     impl Sealed for Decimal {}
+    impl Sealed for Money {}
     impl Sealed for String {}
     impl Sealed for Uuid {}
     impl Sealed for Vec<u8> {}
@@ -84,17 +209,54 @@ mod private {
     impl Sealed for i32 {}
     impl Sealed for i64 {}
     impl Sealed for i8 {}
+    impl Sealed for u8 {}
     impl<'a> Sealed for &'a [u8] {}
     impl<'a> Sealed for &'a str {}
     impl<T> Sealed for Option<T> where T: Sealed {}
+
+    #[cfg(feature = "time")]
+    impl Sealed for time::Date {}
+    #[cfg(feature = "time")]
+    impl Sealed for time::OffsetDateTime {}
+    #[cfg(feature = "time")]
+    impl Sealed for time::PrimitiveDateTime {}
 }
 
-fn guid_to_uuid(g: &Guid) -> Uuid {
-    let b = g.as_bytes();
-    Uuid::from_bytes([
-        b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9], b[10], b[11], b[12], b[13],
-        b[14], b[15],
-    ])
+/// Finds the type of a column by reading it as each decoded type, named as in `check_db_ty`.
+///
+/// Used to describe a conversion error and to choose the decoders of a `DynamicRow`, a null
+/// value gives `None`.
+pub(crate) fn column_type(row: &Row, idx: usize) -> Option<&'static str> {
+    macro_rules! probe {
+        ($($t:ty => $name:expr),*) => {
+            $(
+                let r: std::result::Result<Option<Option<$t>>, tiberius::Error> =
+                    row.0.try_get(idx);
+
+                if let Ok(Some(Some(_))) = r {
+                    return Some($name);
+                }
+            )*
+        };
+    }
+
+    probe!(
+        i64 => "bigint",
+        i32 => "int",
+        i16 => "smallint",
+        i8 => "tinyint",
+        bool => "bit",
+        f64 => "float",
+        f32 => "real",
+        Numeric => "decimal",
+        &str => "nvarchar",
+        &[u8] => "varbinary",
+        NaiveDateTime => "datetime2",
+        NaiveDate => "date",
+        Guid => "uniqueidentifier"
+    );
+
+    None
 }
 
 fn identity<T>(v: T) -> T {
@@ -105,9 +267,17 @@ fn numeric_to_decimal(n: Numeric) -> Decimal {
     decimal::Decimal::new_with_scale(n.value(), n.scale())
 }
 
-fn read<R>(result: std::result::Result<Option<R>, tiberius::Error>, idx: usize) -> Result<R> {
+fn read<R>(
+    row: &Row,
+    result: std::result::Result<Option<R>, tiberius::Error>,
+    idx: usize,
+) -> Result<R> {
     match result {
         Ok(Some(r)) => Ok(r),
+        Ok(None) if idx < row.len() => Err(Error::UnexpectedNull {
+            index: idx,
+            column_name: None,
+        }),
         Ok(None) => Err(Error::FieldNotFound(idx)),
         Err(e) => Err(Error::TiberiusField(e, idx)),
     }
@@ -0,0 +1,166 @@
+use crate::{Connection, Error, Result, SchemaIdent};
+use futures03::future::LocalBoxFuture;
+
+/// The space used by a table, as reported by `sp_spaceused`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpaceUsed {
+    pub name: String,
+    pub rows: i64,
+    pub reserved_kb: i64,
+    pub data_kb: i64,
+    pub index_size_kb: i64,
+    pub unused_kb: i64,
+}
+
+/// A session, as reported by `sp_who2`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionInfo {
+    pub spid: i32,
+    pub status: String,
+    pub login: String,
+    pub host_name: String,
+    /// The spid of the session blocking this one, if any.
+    pub blocked_by: Option<i32>,
+    pub database: Option<String>,
+    pub command: String,
+    pub cpu_time: i64,
+    pub disk_io: i64,
+    pub last_batch: String,
+    pub program_name: String,
+    pub request_id: i32,
+}
+
+impl Connection {
+    /// Returns the space used by a table using `sp_spaceused`.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///     let orders = "dbo.Orders".parse()?;
+    ///     let (connection, space) = connection.space_used(&orders).await?;
+    ///
+    ///     println!("{} rows, {} KB", space.rows, space.reserved_kb);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn space_used<'a>(
+        self,
+        table: &'a SchemaIdent,
+    ) -> LocalBoxFuture<'a, Result<(Self, SpaceUsed)>> {
+        Box::pin(async move {
+            let (conn, rows) = self
+                .query_map("EXEC sp_spaceused @objname = @p1", table.to_string(), |row| {
+                    let rows: &str = row.get(1)?;
+
+                    Ok(SpaceUsed {
+                        name: row.get(0)?,
+                        rows: parse_number(rows)?,
+                        reserved_kb: parse_kb(row.get(2)?)?,
+                        data_kb: parse_kb(row.get(3)?)?,
+                        index_size_kb: parse_kb(row.get(4)?)?,
+                        unused_kb: parse_kb(row.get(5)?)?,
+                    })
+                })
+                .await?;
+
+            match rows.into_iter().next() {
+                Some(space) => Ok((conn, space)),
+                None => Err(Error::String(format!("Table `{}` not found.", table))),
+            }
+        })
+    }
+
+    /// Returns the sessions on the server using `sp_who2`.
+    pub fn who2(self) -> LocalBoxFuture<'static, Result<(Self, Vec<SessionInfo>)>> {
+        let sql = r#"
+            SET NOCOUNT ON;
+
+            CREATE TABLE #who2 (
+                SPID NVARCHAR(256), Status NVARCHAR(256), Login NVARCHAR(256),
+                HostName NVARCHAR(256), BlkBy NVARCHAR(256), DBName NVARCHAR(256),
+                Command NVARCHAR(256), CPUTime NVARCHAR(256), DiskIO NVARCHAR(256),
+                LastBatch NVARCHAR(256), ProgramName NVARCHAR(256), SPID2 NVARCHAR(256),
+                REQUESTID NVARCHAR(256)
+            );
+
+            INSERT INTO #who2 EXEC sp_who2;
+
+            SELECT
+                CAST(LTRIM(RTRIM(SPID)) AS INT),
+                ISNULL(RTRIM(Status), ''),
+                ISNULL(RTRIM(Login), ''),
+                ISNULL(RTRIM(HostName), ''),
+                CAST(NULLIF(LTRIM(RTRIM(BlkBy)), '.') AS INT),
+                RTRIM(DBName),
+                ISNULL(RTRIM(Command), ''),
+                CAST(CPUTime AS BIGINT),
+                CAST(DiskIO AS BIGINT),
+                ISNULL(RTRIM(LastBatch), ''),
+                ISNULL(RTRIM(ProgramName), ''),
+                CAST(REQUESTID AS INT)
+            FROM #who2;
+
+            DROP TABLE #who2;"#;
+
+        self.query_map(sql, (), |row| {
+            Ok(SessionInfo {
+                spid: row.get(0)?,
+                status: row.get(1)?,
+                login: row.get(2)?,
+                host_name: row.get(3)?,
+                blocked_by: row.get(4)?,
+                database: row.get(5)?,
+                command: row.get(6)?,
+                cpu_time: row.get(7)?,
+                disk_io: row.get(8)?,
+                last_batch: row.get(9)?,
+                program_name: row.get(10)?,
+                request_id: row.get(11)?,
+            })
+        })
+    }
+
+    /// Renames a database object using `sp_rename`.
+    ///
+    /// For a column, `object` is `table.column`, and `new_name` is the new column name only.
+    pub fn rename_object<'a>(self, object: &'a str, new_name: &'a str) -> LocalBoxFuture<'a, Result<Self>> {
+        self.execute(
+            "EXEC sp_rename @objname = @p1, @newname = @p2",
+            (object, new_name),
+        )
+    }
+}
+
+fn parse_kb(s: &str) -> Result<i64> {
+    parse_number(s.trim().trim_end_matches("KB"))
+}
+
+fn parse_number(s: &str) -> Result<i64> {
+    s.trim()
+        .parse()
+        .map_err(|_| Error::String(format!("Invalid number `{}`.", s)))
+}
+
+#[test]
+fn parse_kb_works() {
+    assert_eq!(1024, parse_kb("1024 KB").unwrap());
+    assert_eq!(0, parse_kb("0 KB").unwrap());
+    assert_eq!(12, parse_number("12                  ").unwrap());
+    assert!(parse_kb("x KB").is_err());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn who2() -> Result<()> {
+        let (_, sessions) = Connection::from_env("MSSQL_DB").await?.who2().await?;
+        assert!(!sessions.is_empty());
+        Ok(())
+    }
+}
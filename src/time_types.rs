@@ -0,0 +1,288 @@
+//! Support of the [time](https://docs.rs/time) crate, behind the `time` feature.
+//!
+//! The driver decodes the dates as chrono values, which are converted from and to the
+//! `time` types:
+//! - `Date` maps to `date`.
+//! - `PrimitiveDateTime` maps to `datetime` and `datetime2`.
+//! - `OffsetDateTime` is read from a `datetimeoffset` as UTC and bound as a UTC `datetime2`.
+//! - `Time` is bound as a `time` parameter, but the driver does not decode the `time`
+//!   columns, so they must be selected as text, such as `CAST(Col AS VARCHAR(16))`.
+use crate::{row::Row, Error, FromColumn, Parameter, Params, Result, SqlType, SqlValue};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use std::{borrow::Cow, convert::TryFrom};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+/// Implements `SqlValue` and `FromColumn` by reading the chrono type decoded by the driver.
+macro_rules! time_value {
+    ($t:ty, $chrono:ty, $from:expr) => {
+        impl<'a> SqlValue<'a> for $t {
+            fn check_db_ty(v: &str) -> bool {
+                <$chrono>::check_db_ty(v)
+            }
+
+            fn is_nullable() -> bool {
+                false
+            }
+
+            fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
+                <$chrono>::from_row(row, idx).and_then($from)
+            }
+        }
+
+        impl<'a> SqlValue<'a> for Option<$t> {
+            fn check_db_ty(v: &str) -> bool {
+                <$chrono>::check_db_ty(v)
+            }
+
+            fn is_nullable() -> bool {
+                true
+            }
+
+            fn from_row(row: &'a Row, idx: usize) -> Result<Self> {
+                <Option<$chrono>>::from_row(row, idx)?.map($from).transpose()
+            }
+        }
+
+        impl<'a> FromColumn<'a> for $t {
+            type Value = $t;
+
+            fn from_column(v: Self::Value) -> Result<Self> {
+                Ok(v)
+            }
+        }
+    };
+}
+
+time_value!(Date, NaiveDate, date_from_chrono);
+time_value!(PrimitiveDateTime, NaiveDateTime, datetime_from_chrono);
+time_value!(OffsetDateTime, NaiveDateTime, offset_from_chrono);
+
+/// Reads a `time` column selected as text, `hh:mm:ss[.fffffff]`.
+impl<'a> FromColumn<'a> for Time {
+    type Value = &'a str;
+
+    fn from_column(v: Self::Value) -> Result<Self> {
+        parse_time(v)
+    }
+}
+
+/// A date out of the range of chrono, only possible with the `large-dates` feature of
+/// `time`, is also out of the range of SQL Server. It is then bound as text, so that the
+/// query fails with a conversion error.
+impl<'a> Params<'a> for Date {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(match date_to_chrono(self) {
+            Ok(v) => Parameter::Date(Some(v)),
+            Err(_) => out_of_range(SqlType::Date, self),
+        })
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::Date(None))
+    }
+}
+
+/// Bound as text when out of the range of chrono, like `Date`.
+impl<'a> Params<'a> for PrimitiveDateTime {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(match datetime_to_chrono(self) {
+            Ok(v) => Parameter::DateTime(Some(v)),
+            Err(_) => out_of_range(SqlType::DateTime2, self),
+        })
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::DateTime(None))
+    }
+}
+
+impl<'a> Params<'a> for OffsetDateTime {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        let utc = self.to_offset(UtcOffset::UTC);
+        PrimitiveDateTime::new(utc.date(), utc.time()).params(out)
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        PrimitiveDateTime::params_null(out)
+    }
+}
+
+/// Bound as a string declared `TIME`, which the server converts without loss.
+impl<'a> Params<'a> for Time {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        let s = format!(
+            "{:02}:{:02}:{:02}.{:07}",
+            self.hour(),
+            self.minute(),
+            self.second(),
+            self.nanosecond() / 100
+        );
+
+        out.push(Parameter::Typed(
+            SqlType::Time,
+            Box::new(Parameter::String(Some(Cow::Owned(s)))),
+        ))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::Typed(SqlType::Time, Box::new(Parameter::String(None))))
+    }
+}
+
+fn date_from_chrono(v: NaiveDate) -> Result<Date> {
+    let month = Month::try_from(v.month() as u8).map_err(|e| Error::String(e.to_string()))?;
+    Date::from_calendar_date(v.year(), month, v.day() as u8)
+        .map_err(|e| Error::String(e.to_string()))
+}
+
+fn datetime_from_chrono(v: NaiveDateTime) -> Result<PrimitiveDateTime> {
+    let (h, m, s) = (v.hour() as u8, v.minute() as u8, v.second() as u8);
+    let time =
+        Time::from_hms_nano(h, m, s, v.nanosecond()).map_err(|e| Error::String(e.to_string()))?;
+
+    Ok(PrimitiveDateTime::new(date_from_chrono(v.date())?, time))
+}
+
+fn offset_from_chrono(v: NaiveDateTime) -> Result<OffsetDateTime> {
+    Ok(datetime_from_chrono(v)?.assume_utc())
+}
+
+/// Binds a value that chrono cannot hold as text declared with its sql type.
+fn out_of_range<'a, T: ToString>(ty: SqlType, v: T) -> Parameter<'a> {
+    Parameter::Typed(ty, Box::new(Parameter::String(Some(Cow::Owned(v.to_string())))))
+}
+
+// the range of chrono is narrower than the range of time with the `large-dates` feature.
+fn date_to_chrono(v: Date) -> Result<NaiveDate> {
+    NaiveDate::from_ymd_opt(v.year(), u8::from(v.month()).into(), v.day().into())
+        .ok_or_else(|| Error::String(format!("`{}` is out of the range of chrono.", v)))
+}
+
+fn datetime_to_chrono(v: PrimitiveDateTime) -> Result<NaiveDateTime> {
+    let t = v.time();
+    let time = NaiveTime::from_hms_nano_opt(
+        t.hour().into(),
+        t.minute().into(),
+        t.second().into(),
+        t.nanosecond(),
+    )
+    .ok_or_else(|| Error::String(format!("`{}` is not a valid time.", t)))?;
+
+    Ok(NaiveDateTime::new(date_to_chrono(v.date())?, time))
+}
+
+/// Parses `hh:mm:ss` with an optional fraction of up to 9 digits.
+fn parse_time(s: &str) -> Result<Time> {
+    let invalid = || Error::String(format!("`{}` is not a valid time.", s));
+    let (hms, fraction) = match s.find('.') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+
+    let mut parts = hms.split(':').map(|p| p.parse::<u8>().map_err(|_| invalid()));
+    let (h, m, sec) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(h), Some(m), Some(sec), None) => (h?, m?, sec?),
+        _ => return Err(invalid()),
+    };
+
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let nanos = format!("{:0<9}", fraction).parse::<u32>().map_err(|_| invalid())?;
+    Time::from_hms_nano(h, m, sec, nanos).map_err(|_| invalid())
+}
+
+#[test]
+fn chrono_conversions_works() {
+    let date = Date::from_calendar_date(2020, Month::February, 29).unwrap();
+    let dt = PrimitiveDateTime::new(date, Time::from_hms_nano(13, 45, 30, 123_400).unwrap());
+
+    assert_eq!(NaiveDate::from_ymd(2020, 2, 29), date_to_chrono(date).unwrap());
+    assert_eq!(date, date_from_chrono(date_to_chrono(date).unwrap()).unwrap());
+    assert_eq!(dt, datetime_from_chrono(datetime_to_chrono(dt).unwrap()).unwrap());
+
+    // the years of time go up to 999,999 with its `large-dates` feature, 262,142 in chrono
+    let in_range = Date::MAX.year() <= 262_142;
+    assert_eq!(in_range, date_to_chrono(Date::MAX).is_ok());
+}
+
+#[test]
+fn out_of_range_works() {
+    let date = Date::from_calendar_date(2020, Month::February, 29).unwrap();
+    let p = out_of_range(SqlType::Date, date);
+
+    assert!(matches!(&p, Parameter::Typed(SqlType::Date, _)));
+    assert_eq!("2020-02-29", format!("{:?}", p));
+}
+
+#[test]
+fn parse_time_works() {
+    assert_eq!(Time::from_hms(13, 45, 30).unwrap(), parse_time("13:45:30").unwrap());
+    assert_eq!(
+        Time::from_hms_nano(13, 45, 30, 123_456_700).unwrap(),
+        parse_time("13:45:30.1234567").unwrap()
+    );
+    assert!(parse_time("13:45").is_err());
+    assert!(parse_time("25:00:00").is_err());
+    assert!(parse_time("13:45:30.x").is_err());
+}
+
+#[test]
+fn time_params_works() {
+    let mut v = Vec::new();
+    Time::from_hms_milli(8, 5, 0, 250).unwrap().params(&mut v);
+
+    assert!(matches!(&v[0], Parameter::Typed(SqlType::Time, _)));
+    assert_eq!("[08:05:00.2500000]", format!("{:?}", v));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+
+    #[tokio::test]
+    async fn time_types() -> Result<()> {
+        let date = Date::from_calendar_date(2021, Month::March, 14).unwrap();
+        let dt = PrimitiveDateTime::new(date, Time::from_hms_milli(9, 26, 53, 589).unwrap());
+        let time = Time::from_hms(23, 59, 59).unwrap();
+
+        let (_connection, row): (_, (Date, PrimitiveDateTime, OffsetDateTime, Time)) =
+            Connection::from_env("MSSQL_DB")
+                .await?
+                .query_one(
+                    "SELECT @p1, @p2, CAST(@p2 AS DATETIMEOFFSET), CAST(@p3 AS VARCHAR(16))",
+                    (date, dt, time),
+                )
+                .await?;
+
+        assert_eq!((date, dt, dt.assume_utc(), time), row);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn offset_date_time() -> Result<()> {
+        let offset = UtcOffset::from_hms(5, 30, 0).unwrap();
+        let date = Date::from_calendar_date(2021, Month::March, 14).unwrap();
+        let local = PrimitiveDateTime::new(date, Time::from_hms(9, 26, 53).unwrap());
+        let value = local.assume_offset(offset);
+
+        let (_connection, row): (_, (OffsetDateTime, OffsetDateTime)) =
+            Connection::from_env("MSSQL_DB")
+                .await?
+                .query_one(
+                    "SELECT CAST(@p1 AS DATETIMEOFFSET), \
+                     CAST('2021-03-14T09:26:53+05:30' AS DATETIMEOFFSET)",
+                    value,
+                )
+                .await?;
+
+        // read as utc, the same instant as the value bound
+        assert_eq!(value, row.0);
+        assert_eq!(value, row.1);
+        assert_eq!(UtcOffset::UTC, row.1.offset());
+        assert_eq!(Time::from_hms(3, 56, 53).unwrap(), row.1.time());
+        Ok(())
+    }
+}
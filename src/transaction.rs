@@ -1,14 +1,30 @@
 use crate::{
-    utils::{params_to_vec, reduce},
-    Command, Connection, FromRow, Params, Result, Row,
+    context::Context,
+    utils::{batch_sql, params_to_vec, reduce_async},
+    Command, Connection, Error, FromColumn, FromRow, IdlePolicy, Parameter, Params, Result, Row,
+};
+use futures03::{
+    compat::Future01CompatExt,
+    future::{ready, LocalBoxFuture, TryFutureExt},
 };
-use futures03::{compat::Future01CompatExt, future::LocalBoxFuture};
 use futures_state_stream::StateStream;
-use std::{borrow::Cow, ffi::OsStr, fmt::Debug};
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt::Debug,
+    future::Future,
+    time::{Duration, Instant},
+};
 use tiberius::{query::QueryRow, BoxableIo, Transaction as SqlTransaction};
-use tracing::instrument;
+use tracing::{field::Empty, instrument, Span};
 
-pub struct Transaction(pub(super) SqlTransaction<Box<dyn BoxableIo>>);
+pub struct Transaction {
+    pub(crate) inner: SqlTransaction<Box<dyn BoxableIo>>,
+    pub(crate) ctx: Context,
+    /// The number of savepoints opened by `begin_nested`.
+    pub(crate) depth: u32,
+    pub(crate) last_activity: Instant,
+}
 
 impl Command for Transaction {
     fn execute<'a, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<Self>>
@@ -45,13 +61,80 @@ impl Transaction {
         Connection::from_env(key).await?.transaction().await
     }
 
+    /// Starts a nested transaction using a savepoint, so code can open its own unit of
+    /// work without knowing if the caller is already in a transaction.
+    ///
+    /// The nested transaction ends with [commit_nested](#method.commit_nested), keeping its
+    /// changes in the outer transaction, or [rollback_nested](#method.rollback_nested),
+    /// undoing only its changes. [commit](#method.commit) and [rollback](#method.rollback)
+    /// always end the whole transaction.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Result, Transaction};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let tx = Transaction::from_env("MSSQL_DB").await?;
+    ///     let tx = tx.execute("CREATE TABLE #T (Id INT)", ()).await?;
+    ///
+    ///     let tx = tx.begin_nested().await?;
+    ///     let tx = tx.execute("INSERT INTO #T VALUES (1)", ()).await?;
+    ///     let tx = tx.rollback_nested().await?;
+    ///
+    ///     let (tx, count): (_, Vec<i32>) = tx.query("SELECT COUNT(*) FROM #T", ()).await?;
+    ///     assert_eq!(0, count[0]);
+    ///     tx.commit().await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn begin_nested(self) -> LocalBoxFuture<'static, Result<Self>> {
+        let depth = self.depth + 1;
+
+        Box::pin(async move {
+            let sql = format!("SAVE TRANSACTION nested_{}", depth);
+            let mut tx = self.execute(sql, ()).await?;
+            tx.depth = depth;
+            Ok(tx)
+        })
+    }
+
     pub fn commit(self) -> LocalBoxFuture<'static, Result<Connection>> {
         Box::pin(self.commit_imp())
     }
 
     #[instrument(level = "debug", name = "Transaction::commit", skip(self), err)]
     async fn commit_imp(self) -> Result<Connection> {
-        Ok(Connection(self.0.commit().compat().await?))
+        if let Some(idle) = self.watchdog() {
+            self.inner.rollback().compat().await?;
+            return Err(Error::TransactionIdle(idle));
+        }
+
+        Ok(Connection {
+            inner: self.inner.commit().compat().await?,
+            ctx: self.ctx,
+        })
+    }
+
+    /// Ends the innermost nested transaction started with
+    /// [begin_nested](#method.begin_nested), keeping its changes.
+    ///
+    /// The changes are committed or rolled back with the outer transaction.
+    pub fn commit_nested(mut self) -> LocalBoxFuture<'static, Result<Self>> {
+        Box::pin(async move {
+            if self.depth == 0 {
+                return Err(Error::Str("No nested transaction to commit."));
+            }
+
+            self.depth -= 1;
+            Ok(self)
+        })
+    }
+
+    /// The number of nested transactions started with [begin_nested](#method.begin_nested)
+    /// and not yet ended.
+    pub fn depth(&self) -> u32 {
+        self.depth
     }
 
     pub fn execute<'a, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<Self>>
@@ -62,24 +145,72 @@ impl Transaction {
         Box::pin(self.execute_imp(sql, params))
     }
 
-    #[instrument(level = "debug", name = "Transaction::execute", skip(self), err)]
+    /// Execute many sql statements in a single round trip.
+    ///
+    /// See [Connection::execute_batch](struct.Connection.html#method.execute_batch).
+    pub fn execute_batch<'a, S, P>(self, batch: Vec<(S, P)>) -> LocalBoxFuture<'a, Result<Self>>
+    where
+        P: Params<'a>,
+        S: Into<Cow<'static, str>>,
+    {
+        let (sql, params) = batch_sql(batch);
+        self.execute(sql, params)
+    }
+
+    #[instrument(
+        level = "debug",
+        name = "Transaction::execute",
+        skip(self, sql, params),
+        fields(sql = Empty, params = Empty, param_bytes = Empty, affected_rows = Empty),
+        err
+    )]
     async fn execute_imp<'a, S, P>(self, sql: S, params: P) -> Result<Self>
     where
         P: Debug + Params<'a> + 'a,
         S: Debug + Into<Cow<'static, str>> + 'a,
     {
-        let mut p = Vec::new();
-        params.params(&mut p);
+        if let Some(idle) = self.watchdog() {
+            self.inner.rollback().compat().await?;
+            return Err(Error::TransactionIdle(idle));
+        }
 
         let sql = sql.into();
+        let mut p = Vec::new();
+        params.params(&mut p);
+        let started = self.ctx.start(&sql, &mut p).await?;
+        let sql = self.ctx.prepare_sql(sql, &p);
 
-        let (_affected_rows, t) = if p.is_empty() {
-            self.0.simple_exec(sql).compat().await
+        let result = if p.is_empty() {
+            self.inner.simple_exec(sql).compat().await
         } else {
-            self.0.exec(sql, &params_to_vec(&p)).compat().await
-        }?;
+            self.inner.exec(sql, &params_to_vec(&p)).compat().await
+        }
+        .map_err(Error::from);
 
-        Ok(Self(t))
+        self.ctx.on_end(started, &result, |(affected_rows, _)| *affected_rows);
+        let (affected_rows, inner) = result?;
+        Span::current().record("affected_rows", &affected_rows);
+
+        Ok(Self {
+            inner,
+            ctx: self.ctx,
+            depth: self.depth,
+            last_activity: Instant::now(),
+        })
+    }
+
+    /// Execute a sql statement with an `OUTPUT` clause and returns the output rows.
+    pub fn execute_returning<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, Vec<T>)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        self.query_map(sql, params, FromRow::from_row)
     }
 
     pub fn query<'a, T, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<(Self, Vec<T>)>>
@@ -91,18 +222,62 @@ impl Transaction {
         self.query_map(sql, params, FromRow::from_row)
     }
 
+    /// Executes an sql command with an owned sql and owned parameters, returning a `'static`
+    /// future, see [Connection::execute_owned](struct.Connection.html#method.execute_owned).
+    pub fn execute_owned(
+        self,
+        sql: String,
+        params: Vec<Parameter<'static>>,
+    ) -> LocalBoxFuture<'static, Result<Self>> {
+        self.execute(sql, params)
+    }
+
+    /// Queries the database with an owned sql and owned parameters, returning a `'static`
+    /// future, see [Connection::execute_owned](struct.Connection.html#method.execute_owned).
+    pub fn query_owned<T>(
+        self,
+        sql: String,
+        params: Vec<Parameter<'static>>,
+    ) -> LocalBoxFuture<'static, Result<(Self, Vec<T>)>>
+    where
+        T: FromRow + 'static,
+    {
+        self.query(sql, params)
+    }
+
     pub fn query_fold<'a, T, S, P, F>(
         self,
         sql: S,
         params: P,
         init: T,
-        func: F,
+        mut func: F,
     ) -> LocalBoxFuture<'a, Result<(Self, T)>>
     where
         F: FnMut(T, &Row) -> Result<T> + 'a,
         P: Debug + Params<'a> + 'a,
         S: Debug + Into<Cow<'static, str>> + 'a,
         T: 'a,
+    {
+        Box::pin(self.query_fold_imp(sql, params, init, move |acc, row| ready(func(acc, &row))))
+    }
+
+    /// Query the database and reduces the rows using an async function, awaited before
+    /// reading the next row.
+    ///
+    /// See [Connection::query_fold_async](struct.Connection.html#method.query_fold_async).
+    pub fn query_fold_async<'a, T, S, P, F, Fut>(
+        self,
+        sql: S,
+        params: P,
+        init: T,
+        func: F,
+    ) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        F: FnMut(T, Row) -> Fut + 'a,
+        Fut: Future<Output = Result<T>> + 'a,
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: 'a,
     {
         Box::pin(self.query_fold_imp(sql, params, init, func))
     }
@@ -110,10 +285,11 @@ impl Transaction {
     #[instrument(
         level = "debug",
         name = "Transaction::query",
-        skip(self, init, func),
+        skip(self, sql, params, init, func),
+        fields(sql = Empty, params = Empty, param_bytes = Empty, rows = Empty),
         err
     )]
-    async fn query_fold_imp<'a, T, S, P, F>(
+    async fn query_fold_imp<'a, T, S, P, F, Fut>(
         self,
         sql: S,
         params: P,
@@ -121,16 +297,29 @@ impl Transaction {
         mut func: F,
     ) -> Result<(Self, T)>
     where
-        F: FnMut(T, &Row) -> Result<T> + 'a,
+        F: FnMut(T, Row) -> Fut + 'a,
+        Fut: Future<Output = Result<T>> + 'a,
         P: Debug + Params<'a> + 'a,
         S: Debug + Into<Cow<'static, str>> + 'a,
         T: 'a,
     {
+        if let Some(idle) = self.watchdog() {
+            self.inner.rollback().compat().await?;
+            return Err(Error::TransactionIdle(idle));
+        }
+
+        let sql = sql.into();
         let mut p = Vec::new();
         params.params(&mut p);
+        let started = self.ctx.start(&sql, &mut p).await?;
+        let sql = self.ctx.prepare_sql(sql, &p);
+        let mut count = 0;
 
-        let sql = sql.into();
-        let next = move |r, row| func(r, &Row(row));
+        let next = |r, row| {
+            let index = count;
+            count += 1;
+            func(r, Row(row)).map_err(move |e: Error| e.at_row(index))
+        };
 
         let stream: Box<
             dyn StateStream<
@@ -139,14 +328,26 @@ impl Transaction {
                 Error = tiberius::Error,
             >,
         > = if p.is_empty() {
-            Box::new(self.0.simple_query(sql))
+            Box::new(self.inner.simple_query(sql))
         } else {
-            Box::new(self.0.query(sql, &params_to_vec(&p)))
+            Box::new(self.inner.query(sql, &params_to_vec(&p)))
         };
 
-        let (t, rows) = reduce(stream, init, next).await?;
+        let result = reduce_async(stream, init, next).await;
+        self.ctx.on_end(started, &result, |_| count);
+        Span::current().record("rows", &count);
+
+        let (inner, rows) = result?;
 
-        Ok((Self(t), rows))
+        Ok((
+            Self {
+                inner,
+                ctx: self.ctx,
+                depth: self.depth,
+                last_activity: Instant::now(),
+            },
+            rows,
+        ))
     }
 
     pub fn query_map<'a, T, S, P, F>(
@@ -166,14 +367,240 @@ impl Transaction {
             Ok(vec)
         })
     }
+
+    /// Execute sql query and returns the only row, failing if there is none or more.
+    ///
+    /// See [Command::query_one](trait.Command.html#method.query_one).
+    pub fn query_one<'a, T, S, P>(self, sql: S, params: P) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        Command::query_one(self, sql, params)
+    }
+
+    /// Execute sql query and returns the row if there is one, failing if there are more.
+    ///
+    /// See [Command::query_opt](trait.Command.html#method.query_opt).
+    pub fn query_opt<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, Option<T>)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: FromRow + 'a,
+    {
+        Command::query_opt(self, sql, params)
+    }
+
+    /// Execute sql query and returns the first column of the first row, failing if there
+    /// is no row.
+    ///
+    /// See [Command::query_scalar](trait.Command.html#method.query_scalar).
+    pub fn query_scalar<'a, T, S, P>(
+        self,
+        sql: S,
+        params: P,
+    ) -> LocalBoxFuture<'a, Result<(Self, T)>>
+    where
+        P: Debug + Params<'a> + 'a,
+        S: Debug + Into<Cow<'static, str>> + 'a,
+        T: for<'b> FromColumn<'b> + 'a,
+    {
+        Command::query_scalar(self, sql, params)
+    }
+
+    /// Runs `func` inside a transaction, committing if it succeeds.
+    ///
+    /// If `func` returns an error, or the returned future is dropped before completion,
+    /// the transaction is dropped with its connection and the server rolls it back.
+    /// No transaction is left open for the caller to forget.
+    ///
+    /// # Example
+    /// ```
+    /// use mssql_client::{Connection, Result, Transaction};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let connection = Connection::from_env("MSSQL_DB").await?;
+    ///
+    ///     let (connection, count) = Transaction::scope(connection, |tx| async move {
+    ///         let tx = tx.execute("CREATE TABLE #T (Id INT)", ()).await?;
+    ///         let tx = tx.execute("INSERT INTO #T VALUES (1)", ()).await?;
+    ///         let (tx, rows): (_, Vec<i32>) = tx.query("SELECT COUNT(*) FROM #T", ()).await?;
+    ///         Ok((tx, rows[0]))
+    ///     })
+    ///     .await?;
+    ///
+    ///     assert_eq!(1, count);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn scope<'a, T, F, Fut>(
+        connection: Connection,
+        func: F,
+    ) -> LocalBoxFuture<'a, Result<(Connection, T)>>
+    where
+        F: FnOnce(Transaction) -> Fut + 'a,
+        Fut: Future<Output = Result<(Transaction, T)>> + 'a,
+        T: 'a,
+    {
+        Box::pin(async move {
+            let (tx, value) = func(connection.transaction().await?).await?;
+            Ok((tx.commit().await?, value))
+        })
+    }
+
     pub fn rollback(self) -> LocalBoxFuture<'static, Result<Connection>> {
         Box::pin(self.rollback_imp())
     }
 
     #[instrument(level = "trace", name = "Transaction::rollback", skip(self), err)]
     async fn rollback_imp(self) -> Result<Connection> {
-        Ok(Connection(self.0.rollback().compat().await?))
+        Ok(Connection {
+            inner: self.inner.rollback().compat().await?,
+            ctx: self.ctx,
+        })
+    }
+
+    /// Undoes the changes of the innermost nested transaction started with
+    /// [begin_nested](#method.begin_nested) and ends it.
+    pub fn rollback_nested(self) -> LocalBoxFuture<'static, Result<Self>> {
+        let depth = self.depth;
+
+        Box::pin(async move {
+            if depth == 0 {
+                return Err(Error::Str("No nested transaction to roll back."));
+            }
+
+            let sql = format!("ROLLBACK TRANSACTION nested_{}", depth);
+            let mut tx = self.execute(sql, ()).await?;
+            tx.depth = depth - 1;
+            Ok(tx)
+        })
+    }
+
+    /// Applies the idle watchdog before an operation, returns the idle
+    /// duration if the transaction must be rolled back.
+    fn watchdog(&self) -> Option<Duration> {
+        let (max, policy) = self.ctx.idle_watchdog?;
+        let idle = self.last_activity.elapsed();
+
+        if idle <= max {
+            return None;
+        }
+
+        match policy {
+            IdlePolicy::Warn => {
+                tracing::warn!("transaction idle for {:?}", idle);
+                None
+            }
+            IdlePolicy::Rollback => {
+                tracing::warn!("transaction idle for {:?}, rolling back", idle);
+                Some(idle)
+            }
+        }
+    }
+}
+
+/// Options of [Connection::transaction_with](struct.Connection.html#method.transaction_with).
+#[derive(Clone, Debug, Default)]
+pub struct TransactionOptions {
+    distributed: bool,
+    mark: Option<String>,
+    name: Option<String>,
+}
+
+impl TransactionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the transaction, up to 32 letters, digits or underscores.
+    pub fn name<S>(mut self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Places a mark with this description in the transaction log, to restore the
+    /// database up to the mark. Requires a name.
+    pub fn mark<S>(mut self, description: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.mark = Some(description.into());
+        self
     }
+
+    /// Starts a distributed transaction coordinated by MS DTC, `false` by default.
+    ///
+    /// The statements sent to linked servers and the remote procedure calls then run in
+    /// the same atomic transaction. MS DTC must be running on the servers involved.
+    /// Enlisting the connection in an existing distributed transaction, using a
+    /// propagation token, is not supported.
+    pub fn distributed(mut self, distributed: bool) -> Self {
+        self.distributed = distributed;
+        self
+    }
+
+    /// The `BEGIN TRANSACTION` statement.
+    pub(crate) fn begin_sql(&self) -> Result<String> {
+        let mut sql = String::from(if self.distributed {
+            "BEGIN DISTRIBUTED TRANSACTION"
+        } else {
+            "BEGIN TRANSACTION"
+        });
+
+        if let Some(name) = &self.name {
+            let valid = name.len() <= 32
+                && name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+            if !valid {
+                return Err(Error::String(format!("Invalid transaction name `{}`.", name)));
+            }
+
+            sql.push(' ');
+            sql.push_str(name);
+        }
+
+        if let Some(mark) = &self.mark {
+            if self.name.is_none() {
+                return Err(Error::Str("A transaction mark requires a transaction name."));
+            }
+
+            if self.distributed {
+                return Err(Error::Str("A distributed transaction cannot have a mark."));
+            }
+
+            sql.push_str(&format!(" WITH MARK N'{}'", mark.replace('\'', "''")));
+        }
+
+        Ok(sql)
+    }
+}
+
+#[test]
+fn begin_sql_works() {
+    let options = TransactionOptions::new().name("Import_1").mark("before 'import'");
+    assert_eq!(
+        "BEGIN TRANSACTION Import_1 WITH MARK N'before ''import'''",
+        options.begin_sql().unwrap()
+    );
+
+    assert!(TransactionOptions::new().mark("m").begin_sql().is_err());
+    assert!(TransactionOptions::new().name("a; DROP").begin_sql().is_err());
+    assert!(TransactionOptions::new().name("1a").begin_sql().is_err());
+
+    let options = TransactionOptions::new().distributed(true);
+    assert_eq!("BEGIN DISTRIBUTED TRANSACTION", options.begin_sql().unwrap());
+    assert!(options.name("a").mark("m").begin_sql().is_err());
 }
 
 #[cfg(test)]
@@ -216,6 +643,43 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn idle_watchdog() -> Result<()> {
+        let t = Connection::from_env("MSSQL_DB")
+            .await?
+            .transaction_idle_watchdog(Duration::from_millis(1), IdlePolicy::Rollback)
+            .transaction()
+            .await?;
+
+        std::thread::sleep(Duration::from_millis(5));
+
+        match t.execute("DECLARE @a INT = 0", ()).await {
+            Err(Error::TransactionIdle(_)) => Ok(()),
+            _ => panic!("TransactionIdle expected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn nested() -> Result<()> {
+        let tx = Transaction::from_env("MSSQL_DB").await?;
+        let tx = tx.execute("CREATE TABLE #Nested (Id INT)", ()).await?;
+
+        let tx = tx.begin_nested().await?;
+        let tx = tx.execute("INSERT INTO #Nested VALUES (1)", ()).await?;
+        let tx = tx.begin_nested().await?;
+        let tx = tx.execute("INSERT INTO #Nested VALUES (2)", ()).await?;
+        assert_eq!(2, tx.depth());
+
+        let tx = tx.rollback_nested().await?;
+        let tx = tx.commit_nested().await?;
+        assert_eq!(0, tx.depth());
+
+        let (tx, ids): (_, Vec<i32>) = tx.query("SELECT Id FROM #Nested", ()).await?;
+        assert_eq!(vec![1], ids);
+        assert!(tx.commit_nested().await.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn query() -> Result<()> {
         let (_, rows) = Connection::from_env("MSSQL_DB")
@@ -240,4 +704,29 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn scope() -> Result<()> {
+        let connection = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #Scope (Id INT)", ())
+            .await?;
+
+        let (connection, _) = Transaction::scope(connection, |tx| async move {
+            Ok((tx.execute("INSERT INTO #Scope VALUES (1)", ()).await?, ()))
+        })
+        .await?;
+
+        let (connection, rows): (_, Vec<i32>) =
+            connection.query("SELECT Id FROM #Scope", ()).await?;
+        assert_eq!(vec![1], rows);
+
+        let result = Transaction::scope(connection, |tx| async move {
+            Ok((tx.execute("SELECT 1 / 0", ()).await?, ()))
+        })
+        .await;
+
+        assert!(result.is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,84 @@
+use crate::{FromColumn, Parameter, Params, Result};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+    ops::Deref,
+};
+
+/// A string read without its trailing spaces, for the padded values of
+/// fixed-length `char(n)` and `nchar(n)` columns.
+///
+/// # Example
+/// ```
+/// use mssql_client::{Connection, Result, TrimmedString};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let (_connection, rows): (_, Vec<TrimmedString>) = Connection::from_env("MSSQL_DB")
+///         .await?
+///         .query("SELECT CAST('A' AS CHAR(5))", ())
+///         .await?;
+///
+///     assert_eq!("A", &*rows[0]);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TrimmedString(pub String);
+
+impl Deref for TrimmedString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for TrimmedString {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<TrimmedString> for String {
+    fn from(v: TrimmedString) -> Self {
+        v.0
+    }
+}
+
+impl<'a> FromColumn<'a> for TrimmedString {
+    type Value = &'a str;
+
+    fn from_column(v: Self::Value) -> Result<Self> {
+        Ok(TrimmedString(v.trim_end_matches(' ').to_owned()))
+    }
+}
+
+impl<'a> Params<'a> for TrimmedString {
+    fn params(self, out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(Some(Cow::Owned(self.0))))
+    }
+
+    fn params_null(out: &mut Vec<Parameter<'a>>) {
+        out.push(Parameter::String(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connection;
+
+    #[tokio::test]
+    async fn trimmed_string() -> Result<()> {
+        let (_connection, rows): (_, Vec<(TrimmedString, Option<TrimmedString>)>) =
+            Connection::from_env("MSSQL_DB")
+                .await?
+                .query("SELECT CAST(N' A' AS NCHAR(5)), CAST(NULL AS CHAR(2))", ())
+                .await?;
+
+        assert_eq!(" A", &*rows[0].0);
+        assert_eq!(None, rows[0].1);
+        Ok(())
+    }
+}
@@ -1,13 +1,47 @@
-use crate::{Error, Parameter};
+use crate::{Error, Parameter, Params};
 use conn_str::{append_key_value, MsSqlConnStr};
-use futures::Future;
+use futures::{future::poll_fn, Future};
 use futures03::compat::Future01CompatExt;
-use futures_state_stream::StateStream;
-use std::str::FromStr;
+use futures_state_stream::{StateStream, StreamEvent};
+use std::{borrow::Cow, collections::HashMap, io, str::FromStr, sync::Mutex};
 use tiberius::ty::ToSql;
 use tracing::instrument;
 
-pub(crate) fn adjust_conn_str(s: &str) -> Result<String, Error> {
+/// Runs a blocking function, such as a name resolution, on its own thread so that it does
+/// not block the executor.
+pub(crate) async fn unblock<T, F>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = futures03::channel::oneshot::channel();
+
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+
+    rx.await.unwrap_or_else(|_| {
+        Err(Error::Io(io::Error::new(
+            io::ErrorKind::Other,
+            "the blocking thread panicked",
+        )))
+    })
+}
+
+#[test]
+fn unblock_works() {
+    use futures03::executor::block_on;
+
+    assert_eq!(1, block_on(unblock(|| Ok(1))).unwrap());
+    assert!(matches!(
+        block_on(unblock(|| -> Result<(), Error> { panic!("lookup") })),
+        Err(Error::Io(_))
+    ));
+}
+
+/// Adjusts the connection string for the driver. This blocks on the name resolution and
+/// the SQL Server Browser lookup, so it runs through `unblock`.
+pub(crate) fn adjust_conn_str(s: &str, resolve_hostname: bool) -> Result<String, Error> {
     let conn = MsSqlConnStr::from_str(s)?;
 
     let datasource = conn
@@ -15,7 +49,7 @@ pub(crate) fn adjust_conn_str(s: &str) -> Result<String, Error> {
         .filter(|s| !s.trim().is_empty())
         .ok_or(Error::DataSourceNotSpecified)?;
 
-    let datasource = resolve_datasource_into_ip(datasource)?;
+    let datasource = resolve_datasource_into_ip(datasource, resolve_hostname)?;
     let mut out = String::new();
 
     append_key_value(&mut out, "server", &datasource, false);
@@ -48,9 +82,135 @@ pub(crate) fn adjust_conn_str(s: &str) -> Result<String, Error> {
 }
 
 /// Resolve the sql server for replacing in connection str with the ip.
-fn resolve_datasource_into_ip(s: &str) -> Result<String, Error> {
+///
+/// When `resolve_hostname` is `false`, the machine name is kept and resolved by the driver.
+fn resolve_datasource_into_ip(s: &str, resolve_hostname: bool) -> Result<String, Error> {
+    // named pipes and shared memory are not implemented by the driver
+    if let Some(i) = s.find(':') {
+        let protocol = s[..i].trim().to_lowercase();
+
+        if protocol == "np" || protocol == "lpc" {
+            return Err(Error::UnsupportedProtocol(protocol));
+        }
+    }
+
     let mut out = String::new();
+    let (machine, instance, port) = split_datasource(s);
+    let host = if resolve_hostname {
+        resolve(&machine)?
+    } else if machine == "." {
+        "localhost".to_owned()
+    } else {
+        machine
+    };
+
+    out.push_str("tcp:");
+    out.push_str(&host);
+
+    match (instance, port) {
+        (Some(instance), Some(port)) => {
+            out.push_str(&instance);
+            out.push(',');
+            out.push_str(&port);
+        }
+        (Some(instance), None) => match browse_instance_port(&host, &instance[1..]) {
+            Some(port) => {
+                out.push(',');
+                out.push_str(&port.to_string());
+            }
+            None => out.push_str(&instance),
+        },
+        (None, Some(port)) => {
+            out.push(',');
+            out.push_str(&port);
+        }
+        (None, None) => {
+            out.push_str(",1433");
+        }
+    }
+
+    tracing::trace!(
+        "resolved server connection string from `{}` to `{}`",
+        s,
+        out
+    );
+
+    Ok(out)
+}
+
+/// Asks the SQL Server Browser service of the machine for the tcp port of a named instance.
+///
+/// Returns `None` when the service does not answer, the driver then connects to the instance
+/// as before, which only works when it listens on the default port.
+///
+/// The port found is cached for the life of the process, so that the following connections
+/// do not wait for the service.
+fn browse_instance_port(host: &str, instance: &str) -> Option<u16> {
+    use std::{net::UdpSocket, time::Duration};
+
+    static PORTS: Mutex<Option<HashMap<(String, String), u16>>> = Mutex::new(None);
+
+    let key = (host.to_lowercase(), instance.to_lowercase());
+    let ports = || PORTS.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(port) = ports().as_ref().and_then(|m| m.get(&key)) {
+        return Some(*port);
+    }
 
+    let lookup = || -> std::io::Result<Vec<u8>> {
+        let bind = if host.contains(':') { "[::]:0" } else { "0.0.0.0:0" };
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+        socket.connect((host, 1434))?;
+
+        // CLNT_UCAST_INST: the instance name, null terminated
+        let mut request = vec![0x04];
+        request.extend_from_slice(instance.as_bytes());
+        request.push(0);
+        socket.send(&request)?;
+
+        let mut buf = vec![0; 4096];
+        let len = socket.recv(&mut buf)?;
+        buf.truncate(len);
+        Ok(buf)
+    };
+
+    match lookup() {
+        Ok(response) => {
+            let port = parse_browser_response(&response)?;
+            ports().get_or_insert_with(HashMap::new).insert(key, port);
+            Some(port)
+        }
+        Err(e) => {
+            tracing::debug!("SQL Server Browser lookup of `{}` failed: {}", instance, e);
+            None
+        }
+    }
+}
+
+/// Reads the tcp port of an SVR_RESP message, a list of `key;value;` pairs.
+fn parse_browser_response(buf: &[u8]) -> Option<u16> {
+    if buf.len() < 3 || buf[0] != 0x05 {
+        return None;
+    }
+
+    let size = u16::from_le_bytes([buf[1], buf[2]]) as usize;
+    let data = String::from_utf8_lossy(buf.get(3..3 + size)?);
+    let mut tokens = data.split(';');
+
+    while let Some(key) = tokens.next() {
+        let value = tokens.next()?;
+
+        if key.eq_ignore_ascii_case("tcp") {
+            return value.parse().ok();
+        }
+    }
+
+    None
+}
+
+/// Split a data source into its machine, `\instance` and port parts.
+pub(crate) fn split_datasource(s: &str) -> (String, Option<String>, Option<String>) {
     let instance_sep = s.find('\\');
     let port_sep = s.find(',');
     let has_tcp = s.to_lowercase().starts_with("tcp:");
@@ -60,17 +220,12 @@ fn resolve_datasource_into_ip(s: &str) -> Result<String, Error> {
         tcp_sep = 4;
     }
 
-    out.push_str("tcp:");
-
     let m = std::cmp::min(
         port_sep.unwrap_or_else(|| s.len()),
         instance_sep.unwrap_or_else(|| s.len()),
     );
 
     let machine = s.chars().take(m).skip(tcp_sep).collect::<String>();
-    let machine = resolve(&machine)?;
-
-    out.push_str(&machine);
 
     let instance = instance_sep.map(|i| {
         s.chars()
@@ -83,63 +238,92 @@ fn resolve_datasource_into_ip(s: &str) -> Result<String, Error> {
         .map(|i| s.chars().skip(i + 1).collect::<String>())
         .filter(|p| !p.is_empty());
 
-    match (instance, port) {
-        (Some(instance), Some(port)) => {
-            out.push_str(&instance);
-            out.push(',');
-            out.push_str(&port);
-        }
-        (Some(instance), None) => {
-            out.push_str(&instance);
-        }
-        (None, Some(port)) => {
-            out.push(',');
-            out.push_str(&port);
-        }
-        (None, None) => {
-            out.push_str(",1433");
-        }
-    }
+    (machine, instance, port)
+}
 
-    tracing::trace!(
-        "resolved server connection string from `{}` to `{}`",
-        s,
-        out
+#[test]
+fn split_datasource_works() {
+    assert_eq!(
+        (
+            "localhost".to_owned(),
+            Some(r#"\Sql2017"#.to_owned()),
+            Some("1433".to_owned())
+        ),
+        split_datasource(r#"tcp:localhost\Sql2017,1433"#)
     );
 
-    Ok(out)
+    assert_eq!((".".to_owned(), None, None), split_datasource("."));
+}
+
+#[test]
+fn parse_browser_response_works() {
+    let data = "ServerName;SRV;InstanceName;SQL2017;IsClustered;No;\
+                Version;14.0.1000.169;tcp;50123;;";
+    let mut buf = vec![0x05];
+    buf.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    buf.extend_from_slice(data.as_bytes());
+
+    assert_eq!(Some(50123), parse_browser_response(&buf));
+    assert_eq!(None, parse_browser_response(&buf[..20]));
+    assert_eq!(None, parse_browser_response(&[0x05, 3, 0, b'n', b'p', b';']));
+}
+
+#[test]
+fn resolve_datasource_unsupported_protocol() {
+    assert!(matches!(
+        resolve_datasource_into_ip(r#"np:\\.\pipe\sql\query"#, true),
+        Err(Error::UnsupportedProtocol(p)) if p == "np"
+    ));
+
+    assert!(matches!(
+        resolve_datasource_into_ip("LPC:(local)", true),
+        Err(Error::UnsupportedProtocol(p)) if p == "lpc"
+    ));
+}
+
+#[test]
+fn keep_hostname_works() {
+    assert_eq!(
+        "tcp:sql.example.local,1433",
+        resolve_datasource_into_ip("tcp:sql.example.local", false).unwrap()
+    );
+
+    assert_eq!(
+        "tcp:localhost,1500",
+        resolve_datasource_into_ip(".,1500", false).unwrap()
+    );
 }
 
 #[test]
 fn resolve_datasource_into_ip_works() {
-    assert!(resolve_datasource_into_ip(r#"tcp:localhost\Sql2017"#).is_ok());
+    assert!(resolve_datasource_into_ip(r#"tcp:localhost\Sql2017"#, true).is_ok());
 
-    assert!(resolve_datasource_into_ip(r#"tcp:localhost"#).is_ok());
+    assert!(resolve_datasource_into_ip(r#"tcp:localhost"#, true).is_ok());
 
     assert_eq!(
         "tcp:127.0.0.1,1433",
-        resolve_datasource_into_ip(r#"tcp:localhost,1433"#).unwrap()
+        resolve_datasource_into_ip(r#"tcp:localhost,1433"#, true).unwrap()
     );
 
     assert_eq!(
         "tcp:172.18.71.36,1433",
-        resolve_datasource_into_ip(r#"tcp:172.18.71.36,1433"#).unwrap()
+        resolve_datasource_into_ip(r#"tcp:172.18.71.36,1433"#, true).unwrap()
     );
 
-    assert!(resolve_datasource_into_ip(r#"tcp:localhost"#).is_ok());
+    assert!(resolve_datasource_into_ip(r#"tcp:localhost"#, true).is_ok());
 
-    assert!(resolve_datasource_into_ip(r#"tcp:."#).is_ok());
+    assert!(resolve_datasource_into_ip(r#"tcp:."#, true).is_ok());
 
-    assert!(resolve_datasource_into_ip(r#".\Sql2017"#).is_ok());
+    assert!(resolve_datasource_into_ip(r#".\Sql2017"#, true).is_ok());
 
-    assert!(resolve_datasource_into_ip(r#"."#).is_ok());
+    assert!(resolve_datasource_into_ip(r#"."#, true).is_ok());
 
-    assert!(resolve_datasource_into_ip(r#".,1433"#).is_ok());
+    assert!(resolve_datasource_into_ip(r#".,1433"#, true).is_ok());
 
-    assert!(resolve_datasource_into_ip(r#".\Sql2017,1433"#).is_ok());
+    assert!(resolve_datasource_into_ip(r#".\Sql2017,1433"#, true).is_ok());
 }
 
-fn resolve(mut host: &str) -> Result<String, Error> {
+pub(crate) fn resolve(mut host: &str) -> Result<String, Error> {
     use std::net::ToSocketAddrs;
 
     if host == "." {
@@ -173,6 +357,161 @@ fn resolve_works() {
     assert!(resolve(&std::env::var("COMPUTERNAME").unwrap()).is_ok());
 }
 
+/// Splits a multi-part object name on the dots that are not inside brackets,
+/// removing the brackets of the quoted parts.
+pub(crate) fn split_name(name: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut quoted = false;
+    let mut chars = name.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if !quoted && part.trim().is_empty() => {
+                part.clear();
+                quoted = true;
+            }
+            ']' if quoted && chars.peek() == Some(&']') => {
+                chars.next();
+                part.push(']');
+            }
+            ']' if quoted => {
+                // keep the part as is, whitespace up to the next dot is dropped
+                parts.push(part.split_off(0));
+                quoted = false;
+
+                while chars.next_if(|c| *c != '.').is_some() {}
+
+                if chars.next().is_none() {
+                    return parts;
+                }
+            }
+            '.' if !quoted => parts.push(part.split_off(0).trim().to_owned()),
+            c => part.push(c),
+        }
+    }
+
+    parts.push(part.trim().to_owned());
+    parts
+}
+
+/// Quote a single identifier with brackets.
+pub(crate) fn quote_ident(ident: &str) -> String {
+    format!("[{}]", ident.replace(']', "]]"))
+}
+
+#[test]
+fn split_name_works() {
+    assert_eq!(vec!["dbo", "Orders"], split_name("dbo.Orders"));
+    assert_eq!(vec!["my.db", "dbo", "T"], split_name("[my.db]. dbo .[T]"));
+    assert_eq!(vec!["db", "", "T"], split_name("db..T"));
+    assert_eq!(vec![" a]b "], split_name("[ a]]b ]"));
+}
+
+/// Replaces the string literals of a sql statement with `'***'`, to keep the values
+/// out of the logs.
+///
+/// Bracketed identifiers are kept. A quote in a comment masks the rest of the statement.
+///
+/// # Example
+/// ```
+/// use mssql_client::{scrub_literals, ConnectionFactory, Result};
+///
+/// fn main() -> Result<()> {
+///     let connection_factory = ConnectionFactory::from_env("MSSQL_DB")?
+///         .redact_sql(scrub_literals);
+///
+///     assert_eq!("WHERE [a'b] = N'***'", scrub_literals("WHERE [a'b] = N'it''s'"));
+///     Ok(())
+/// }
+/// ```
+pub fn scrub_literals(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        out.push(c);
+
+        match c {
+            '\'' => {
+                out.push_str("***'");
+
+                // '' is an escaped quote
+                while let Some(c) = chars.next() {
+                    if c == '\'' {
+                        if chars.peek() != Some(&'\'') {
+                            break;
+                        }
+
+                        chars.next();
+                    }
+                }
+            }
+            '[' => {
+                // ]] is an escaped bracket
+                while let Some(c) = chars.next() {
+                    out.push(c);
+
+                    if c == ']' {
+                        if chars.peek() != Some(&']') {
+                            break;
+                        }
+
+                        out.extend(chars.next());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+#[test]
+fn scrub_literals_works() {
+    assert_eq!(
+        "SELECT '***', N'***' FROM [T'x]]'] WHERE a = 1",
+        scrub_literals("SELECT 'a''b', N'c' FROM [T'x]]'] WHERE a = 1")
+    );
+    assert_eq!("'***'", scrub_literals("'open"));
+}
+
+/// Returns the highest `@pN` placeholder referenced by the sql, ignoring the string literals.
+pub(crate) fn max_placeholder(sql: &str) -> usize {
+    let sql = scrub_literals(sql);
+    let bytes = sql.as_bytes();
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'@' || b == b'#';
+    let mut max = 0;
+
+    for (i, _) in sql.match_indices('@') {
+        if i > 0 && is_ident(bytes[i - 1]) {
+            continue;
+        }
+
+        match bytes.get(i + 1) {
+            Some(b'p') | Some(b'P') => {}
+            _ => continue,
+        }
+
+        let digits = bytes[i + 2..].iter().take_while(|b| b.is_ascii_digit()).count();
+        let end = i + 2 + digits;
+
+        if digits > 0 && bytes.get(end).map_or(true, |&b| !is_ident(b)) {
+            max = max.max(sql[i + 2..end].parse().unwrap_or(0));
+        }
+    }
+
+    max
+}
+
+#[test]
+fn max_placeholder_works() {
+    assert_eq!(0, max_placeholder("SELECT 1"));
+    assert_eq!(3, max_placeholder("SELECT @p1, @P3 WHERE @p2 = 1"));
+    assert_eq!(1, max_placeholder("SELECT @p1, '@p2', @@p3, @p4x, @pa"));
+}
+
 pub fn replace_params(sql: &mut String, param: &str, replace: &str) {
     enum State {
         None,
@@ -180,6 +519,8 @@ pub fn replace_params(sql: &mut String, param: &str, replace: &str) {
         Param(usize),
     }
 
+    // sql variables are case insensitive
+    let param = param.to_lowercase();
     let mut vec = Vec::new();
     let mut state = State::None;
 
@@ -234,6 +575,83 @@ fn replace_params_works() {
     replace_params(&mut s, "p2", "param3");
 
     assert_eq!("SELECT @param1,@param2,@param3 FROM Test", &s);
+
+    let mut s = "SELECT @Id WHERE @id = @id2".to_owned();
+    replace_params(&mut s, "ID", "P1");
+    assert_eq!("SELECT @P1 WHERE @P1 = @id2", &s);
+}
+
+/// Concatenate sql statements into a single batch, renumbering the `@pN`
+/// placeholders of each statement to follow the parameters of the previous ones.
+pub(crate) fn batch_sql<'a, S, P>(batch: Vec<(S, P)>) -> (String, Vec<Parameter<'a>>)
+where
+    P: Params<'a>,
+    S: Into<Cow<'static, str>>,
+{
+    let mut out = String::new();
+    let mut params = Vec::new();
+
+    for (sql, p) in batch {
+        let offset = params.len();
+        p.params(&mut params);
+
+        let mut sql = sql.into().into_owned();
+
+        // descending, so that a renumbered placeholder is never renumbered again.
+        for i in (1..=params.len() - offset).rev() {
+            replace_params(&mut sql, &format!("p{}", i), &format!("P{}", offset + i));
+        }
+
+        if !out.is_empty() {
+            out.push_str(";\n");
+        }
+
+        out.push_str(sql.trim_end().trim_end_matches(';'));
+    }
+
+    (out, params)
+}
+
+#[test]
+fn batch_sql_works() {
+    let (sql, params) = batch_sql(vec![
+        ("INSERT T VALUES (@p1, @p2);", (1, 2)),
+        ("INSERT T VALUES (@p2, @p1)", (3, 4)),
+        ("DELETE T WHERE Id = @P1", (5, 6)),
+    ]);
+
+    assert_eq!(
+        "INSERT T VALUES (@P1, @P2);\nINSERT T VALUES (@P4, @P3);\nDELETE T WHERE Id = @P5",
+        &sql
+    );
+    assert_eq!(6, params.len());
+}
+
+/// Like `reduce`, but awaits `next` before reading the next item.
+///
+/// After an error, the remaining items are read and discarded to reach the state.
+pub(crate) async fn reduce_async<B, F, Fut, S>(
+    mut stream: S,
+    init: B,
+    mut next: F,
+) -> Result<(S::State, B), Error>
+where
+    F: FnMut(B, S::Item) -> Fut,
+    Fut: std::future::Future<Output = Result<B, Error>>,
+    S: StateStream<Error = tiberius::Error>,
+{
+    let mut acc = Ok(init);
+
+    loop {
+        match poll_fn(|| stream.poll()).compat().await? {
+            StreamEvent::Next(item) => {
+                if let Ok(b) = acc {
+                    acc = next(b, item).await;
+                }
+            }
+            StreamEvent::Done(state) => return acc.map(|b| (state, b)),
+        }
+    }
 }
 
 #[instrument(level = "trace", skip(stream, init, next))]
@@ -0,0 +1,148 @@
+//! Helpers for the `uniqueidentifier` values of SQL Server.
+//!
+//! SQL Server stores the first three groups of a GUID in little endian, while
+//! [Uuid](https://docs.rs/uuid) keeps the bytes in the order they are displayed. It also
+//! sorts the GUIDs starting from their last 6 bytes, so random GUIDs used as clustered keys
+//! fragment the index; [new_sequential](fn.new_sequential.html) generates GUIDs that are
+//! sorted in the order they are created, like `NEWSEQUENTIALID()`.
+//!
+//! # Example
+//! ```
+//! use mssql_client::{uuid_ext, Connection, Result};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let _connection = Connection::from_env("MSSQL_DB")
+//!         .await?
+//!         .execute("CREATE TABLE #T (Id UNIQUEIDENTIFIER PRIMARY KEY)", ())
+//!         .await?
+//!         .execute("INSERT #T VALUES (@p1)", uuid_ext::new_sequential())
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+/// The GUID type of the driver, used by the parameters and the decoded columns.
+pub use tiberius::ty::Guid;
+
+/// Bits of the counter that orders the GUIDs created in the same millisecond.
+const COUNTER_BITS: u32 = 14;
+
+/// Last timestamp in milliseconds, shifted left by `COUNTER_BITS`, plus the counter.
+static LAST: AtomicU64 = AtomicU64::new(0);
+
+/// Creates a GUID that SQL Server sorts after all the GUIDs previously created by the
+/// process.
+///
+/// The last 6 bytes hold the milliseconds since the unix epoch and the 4th group a
+/// counter for the GUIDs of the same millisecond, the other bytes are random.
+pub fn new_sequential() -> Uuid {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+
+    let next = |last: u64| (now << COUNTER_BITS).max(last + 1);
+
+    // the closure always returns Some, the update cannot fail
+    let last = LAST
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| Some(next(v)))
+        .unwrap_or_else(|v| v);
+
+    sequential_from(next(last), *Uuid::new_v4().as_bytes())
+}
+
+/// Writes the timestamp and the counter of `value` over random bytes.
+fn sequential_from(value: u64, mut b: [u8; 16]) -> Uuid {
+    let millis = (value >> COUNTER_BITS).to_be_bytes();
+    let counter = (value & ((1 << COUNTER_BITS) - 1)) as u16;
+
+    b[10..].copy_from_slice(&millis[2..]);
+    // keeps the RFC 4122 variant in the 2 high bits
+    b[8] = 0x80 | (counter >> 8) as u8;
+    b[9] = counter as u8;
+
+    Uuid::from_bytes(b)
+}
+
+/// Returns the bytes of the GUID in the order stored by SQL Server.
+pub fn to_sql_bytes(id: &Uuid) -> [u8; 16] {
+    swap(id.as_bytes())
+}
+
+/// Reads a GUID from the bytes in the order stored by SQL Server.
+pub fn from_sql_bytes(b: &[u8; 16]) -> Uuid {
+    Uuid::from_bytes(swap(b))
+}
+
+/// Converts a GUID into the value sent to SQL Server by the driver.
+pub fn to_sql_guid(id: Uuid) -> Guid {
+    Guid::from_bytes(&to_sql_bytes(&id))
+}
+
+/// Converts a GUID decoded by the driver.
+pub fn from_sql_guid(g: &Guid) -> Uuid {
+    from_sql_bytes(g.as_bytes())
+}
+
+/// Reverses the first three groups, the conversion is the same in both directions.
+fn swap(b: &[u8; 16]) -> [u8; 16] {
+    [
+        b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9], b[10], b[11], b[12], b[13],
+        b[14], b[15],
+    ]
+}
+
+#[test]
+fn sql_bytes_works() {
+    let id: Uuid = "00112233-4455-6677-8899-aabbccddeeff".parse().unwrap();
+    let b = to_sql_bytes(&id);
+
+    assert_eq!([0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88], b[..9]);
+    assert_eq!(id, from_sql_bytes(&b));
+    assert_eq!(id, from_sql_guid(&to_sql_guid(id)));
+    assert_eq!(&b, to_sql_guid(id).as_bytes());
+}
+
+#[test]
+fn new_sequential_works() {
+    // the order used by SQL Server to compare the uniqueidentifier
+    fn key(id: &Uuid) -> Vec<u8> {
+        let b = id.as_bytes();
+        [&b[10..], &b[8..10], &b[6..8], &b[4..6], &b[..4]].concat()
+    }
+
+    let ids = (0..1000).map(|_| new_sequential()).collect::<Vec<_>>();
+    assert!(ids.windows(2).all(|w| key(&w[0]) < key(&w[1])));
+
+    let id = sequential_from((1 << COUNTER_BITS) + 0x3fff, [0; 16]);
+    assert_eq!("00000000-0000-0000-bfff-000000000001", id.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Result};
+
+    #[tokio::test]
+    async fn sequential_order() -> Result<()> {
+        let ids = (0..100).map(|_| new_sequential()).collect::<Vec<_>>();
+        let mut connection = Connection::from_env("MSSQL_DB")
+            .await?
+            .execute("CREATE TABLE #T (Id UNIQUEIDENTIFIER)", ())
+            .await?;
+
+        for id in ids.iter().rev() {
+            connection = connection.execute("INSERT #T VALUES (@p1)", id).await?;
+        }
+
+        let (_, rows): (_, Vec<Uuid>) =
+            connection.query("SELECT Id FROM #T ORDER BY Id", ()).await?;
+        assert_eq!(ids, rows);
+        Ok(())
+    }
+}